@@ -0,0 +1,220 @@
+//! a persistent, file-backed cache of single-point energies, allowing related
+//! expansions to skip geometries that have already been computed
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use intder::Intder;
+
+use crate::{disp_id, Disps};
+
+/// return a stable hash of the reference geometry and internal coordinate
+/// definitions in `intder`. coordinates are rounded to 1e-8 bohr so that
+/// round-tripping the geometry through a file doesn't change the hash. the
+/// displacements in `intder` are not included
+pub fn reference_hash(intder: &Intder) -> u64 {
+    let mut s = String::new();
+    for (atom, g) in intder.atoms.iter().zip(&intder.geom.0) {
+        s.push_str(&format!(
+            "{} {:.8} {:.8} {:.8}\n",
+            atom.label, g[0], g[1], g[2]
+        ));
+    }
+    for siic in &intder.simple_internals {
+        s.push_str(&format!("{siic}\n"));
+    }
+    for sic in &intder.symmetry_internals {
+        for c in sic {
+            s.push_str(&format!("{c:.8} "));
+        }
+        s.push('\n');
+    }
+    fnv1a(s.as_bytes())
+}
+
+/// the 64-bit FNV-1a hash of `bytes`. unlike the hashers in std, this is
+/// guaranteed not to change between runs or compiler versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325_u64;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Key {
+    reference: u64,
+    step_size: String,
+    id: String,
+}
+
+impl Key {
+    fn new(reference: u64, step_size: f64, id: String) -> Self {
+        Self {
+            reference,
+            step_size: step_size.to_string(),
+            id,
+        }
+    }
+}
+
+/// an energy cache keyed by the reference hash from [reference_hash], the step
+/// size, and the displacement. each entry is stored on its own line of the
+/// backing file as `reference step_size id energy`
+#[derive(Clone, Debug)]
+pub struct EnergyCache {
+    path: PathBuf,
+    entries: HashMap<Key, f64>,
+}
+
+impl EnergyCache {
+    /// open the cache stored in `path`, starting with an empty cache if the
+    /// file does not exist yet
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = HashMap::new();
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)?;
+            for (i, line) in contents.lines().enumerate() {
+                let fields: Vec<_> = line.split_whitespace().collect();
+                let entry = match fields.as_slice() {
+                    [] => continue,
+                    [reference, step_size, id, energy] => {
+                        u64::from_str_radix(reference, 16)
+                            .ok()
+                            .zip(energy.parse().ok())
+                            .map(|(r, e)| {
+                                (
+                                    Key {
+                                        reference: r,
+                                        step_size: step_size.to_string(),
+                                        id: id.to_string(),
+                                    },
+                                    e,
+                                )
+                            })
+                    }
+                    _ => None,
+                };
+                let Some((key, energy)) = entry else {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "invalid entry on line {} of {}: `{line}`",
+                            i + 1,
+                            path.display()
+                        ),
+                    ));
+                };
+                entries.insert(key, energy);
+            }
+        }
+        Ok(Self { path, entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// return the cached energy for `disp`, if any
+    pub fn get(
+        &self,
+        reference: u64,
+        step_size: f64,
+        disp: &[i8],
+    ) -> Option<f64> {
+        self.entries
+            .get(&Key::new(reference, step_size, disp_id(disp)))
+            .copied()
+    }
+
+    /// add `energy` for `disp` to the cache, replacing any existing entry.
+    /// call [EnergyCache::save] to write the changes to disk
+    pub fn insert(
+        &mut self,
+        reference: u64,
+        step_size: f64,
+        disp: &[i8],
+        energy: f64,
+    ) {
+        self.entries
+            .insert(Key::new(reference, step_size, disp_id(disp)), energy);
+    }
+
+    /// insert each of the energies in `disps` and `energies` into the cache
+    pub fn extend(
+        &mut self,
+        reference: u64,
+        step_size: f64,
+        disps: &Disps,
+        energies: &[f64],
+    ) {
+        assert_eq!(disps.len(), energies.len());
+        for (disp, energy) in disps.iter().zip(energies) {
+            self.insert(reference, step_size, disp, *energy);
+        }
+    }
+
+    /// return the displacements in `disps` without a cached energy, which
+    /// still need to be computed
+    pub fn missing(
+        &self,
+        reference: u64,
+        step_size: f64,
+        disps: &Disps,
+    ) -> Disps {
+        Disps(
+            disps
+                .0
+                .filter(|d| self.get(reference, step_size, d).is_none()),
+        )
+    }
+
+    /// return the cached energies for all of `disps`, or the identifier of the
+    /// first displacement missing from the cache as an error
+    pub fn energies(
+        &self,
+        reference: u64,
+        step_size: f64,
+        disps: &Disps,
+    ) -> Result<Vec<f64>, String> {
+        disps
+            .iter()
+            .map(|d| {
+                self.get(reference, step_size, d).ok_or_else(|| disp_id(d))
+            })
+            .collect()
+    }
+
+    /// write the whole cache back to its file, sorted for stable diffs
+    pub fn save(&self) -> std::io::Result<()> {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by(|a, b| {
+            (a.0.reference, &a.0.step_size, &a.0.id).cmp(&(
+                b.0.reference,
+                &b.0.step_size,
+                &b.0.id,
+            ))
+        });
+        let mut f = BufWriter::new(File::create(&self.path)?);
+        for (key, energy) in entries {
+            writeln!(
+                f,
+                "{:016x} {} {} {energy}",
+                key.reference, key.step_size, key.id
+            )?;
+        }
+        f.flush()
+    }
+}
@@ -0,0 +1,71 @@
+//! counts of the force constants kept and eliminated by the symmetry checks
+//! in a [crate::Taylor] expansion
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use symm::Irrep;
+
+/// the number of force constants in one group of a [Census]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Count {
+    /// the force constants that survive the checks
+    pub kept: usize,
+    /// the force constants eliminated by the checks
+    pub pruned: usize,
+}
+
+/// the force constants of a [crate::Taylor] expansion grouped by derivative
+/// order and then by the sorted, unique irreps of the coordinates involved,
+/// as returned by [crate::Taylor::census]. the constant term is not included
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Census(pub BTreeMap<usize, BTreeMap<Vec<Irrep>, Count>>);
+
+impl Census {
+    /// the total count of each order
+    pub fn totals(&self) -> BTreeMap<usize, Count> {
+        self.0
+            .iter()
+            .map(|(&order, groups)| {
+                let total =
+                    groups.values().fold(Count::default(), |acc, c| Count {
+                        kept: acc.kept + c.kept,
+                        pruned: acc.pruned + c.pruned,
+                    });
+                (order, total)
+            })
+            .collect()
+    }
+}
+
+impl Display for Census {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:>5} {:<20} {:>10} {:>10}",
+            "Order", "Irreps", "Kept", "Pruned"
+        )?;
+        let totals = self.totals();
+        for (order, groups) in &self.0 {
+            for (irreps, count) in groups {
+                let irreps: Vec<_> =
+                    irreps.iter().map(|i| i.to_string()).collect();
+                writeln!(
+                    f,
+                    "{:5} {:<20} {:10} {:10}",
+                    order,
+                    irreps.join(" "),
+                    count.kept,
+                    count.pruned
+                )?;
+            }
+            let total = totals[order];
+            writeln!(
+                f,
+                "{:5} {:<20} {:10} {:10}",
+                order, "total", total.kept, total.pruned
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 use std::ops::{Index, IndexMut};
 
-#[derive(Clone, Debug, Default, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Checks(pub [Vec<usize>; 3]);
 
 impl Index<(usize, usize)> for Checks {
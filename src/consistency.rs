@@ -0,0 +1,97 @@
+//! checks that the energies of displacements related by symmetry agree, as
+//! they must for the symmetry checks on the expansion to be valid. mismatches
+//! usually indicate a broken-symmetry SCF solution or a SIC assigned to the
+//! wrong irrep
+
+use std::fmt::Display;
+
+use symm::{Irrep, PointGroup};
+
+use crate::{generators, Disps};
+
+/// a pair of displacements related by a symmetry operation whose energies
+/// differ by more than the tolerance
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    /// the index of the first displacement
+    pub disp: usize,
+    /// the index of its image under the operation
+    pub image: usize,
+    /// the energy of `image` minus the energy of `disp`
+    pub diff: f64,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:5} {:5} {:12.3e}",
+            self.disp + 1,
+            self.image + 1,
+            self.diff
+        )
+    }
+}
+
+/// compare the `energies` of each of the `disps` with those of its images
+/// under the symmetry operations of `pg`, where the SICs transform as
+/// `irreps`, and return the pairs that differ by more than `tol`. each pair
+/// is reported once, with `disp` < `image`. returns None if `pg` is not
+/// supported
+pub fn check_energies(
+    pg: &PointGroup,
+    irreps: &[Irrep],
+    disps: &Disps,
+    energies: &[f64],
+    tol: f64,
+) -> Option<Vec<Mismatch>> {
+    assert_eq!(disps.len(), energies.len());
+    let generators = generators(pg)?;
+    // the sign of each SIC under every operation of the group other than the
+    // identity, from the products of the generators
+    let ops: Vec<Vec<i8>> = (1..1 << generators.len())
+        .map(|bits: usize| {
+            irreps
+                .iter()
+                .map(|irrep| {
+                    let odd = generators
+                        .iter()
+                        .enumerate()
+                        .filter(|(g, odd)| {
+                            bits >> g & 1 == 1 && odd.contains(irrep)
+                        })
+                        .count();
+                    if odd % 2 == 0 {
+                        1
+                    } else {
+                        -1
+                    }
+                })
+                .collect()
+        })
+        .filter(|signs: &Vec<i8>| signs.iter().any(|&s| s < 0))
+        .collect();
+
+    let mut ret = Vec::new();
+    for (i, disp) in disps.iter().enumerate() {
+        for signs in &ops {
+            let image: Vec<_> =
+                disp.iter().zip(signs).map(|(d, s)| d * s).collect();
+            let Some(j) = disps.position(&image) else {
+                continue;
+            };
+            let diff = energies[j] - energies[i];
+            if j > i
+                && diff.abs() > tol
+                && !ret.iter().any(|m: &Mismatch| m.disp == i && m.image == j)
+            {
+                ret.push(Mismatch {
+                    disp: i,
+                    image: j,
+                    diff,
+                });
+            }
+        }
+    }
+    Some(ret)
+}
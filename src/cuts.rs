@@ -0,0 +1,164 @@
+//! one- and two-dimensional cuts through the potential energy surface along
+//! chosen SICs, comparing a fitted expansion with the computed energies
+
+use std::io::Write;
+
+use crate::{fit::FittedTaylor, Disps};
+
+/// the output format for a [Cut]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// comma-separated values with a header line. missing energies are left
+    /// empty
+    Csv,
+    /// whitespace-separated columns with a commented header and blank lines
+    /// between the rows of a 2D grid, as expected by gnuplot's splot. missing
+    /// energies are written as ?
+    Gnuplot,
+}
+
+/// a point in a [Cut]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CutPoint {
+    /// the displacement along each coordinate of the cut, in Ångstrom and
+    /// radians
+    pub x: Vec<f64>,
+    /// the energy from the fitted expansion
+    pub fitted: Option<f64>,
+    /// the computed energy, if the point is one of the displacements
+    pub computed: Option<f64>,
+}
+
+/// a regular grid of points along one or two SICs, with the remaining SICs
+/// held at the reference
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cut {
+    /// the 0-based indices of the SICs spanned by the cut
+    pub coords: Vec<usize>,
+    /// the points of the grid, with the last coordinate varying fastest
+    pub points: Vec<CutPoint>,
+}
+
+impl Cut {
+    /// build a cut along `coords` on a grid of multiples of `step_size`. the
+    /// computed energies come from the entries of `disps` that are zero
+    /// outside of `coords`, with their corresponding `energies`, and the
+    /// fitted energies from `fitted`. the grid extends `half_width` steps in
+    /// each direction, defaulting to the largest displacement along any of
+    /// `coords`
+    pub fn new(
+        coords: &[usize],
+        disps: &Disps,
+        energies: Option<&[f64]>,
+        fitted: Option<&FittedTaylor>,
+        step_size: f64,
+        half_width: Option<i8>,
+    ) -> Self {
+        assert!(
+            matches!(coords.len(), 1 | 2),
+            "cuts must span one or two coordinates"
+        );
+        let on_cut = |d: &[i8]| {
+            d.iter()
+                .enumerate()
+                .all(|(i, &s)| s == 0 || coords.contains(&i))
+        };
+        let half_width = half_width.unwrap_or_else(|| {
+            disps
+                .iter()
+                .filter(|d| on_cut(d))
+                .flat_map(|d| coords.iter().map(move |&c| d[c].abs()))
+                .max()
+                .unwrap_or(0)
+        });
+
+        let ncoord = disps.iter().next().map_or(0, |d| d.len());
+        let mut grid: Vec<Vec<i8>> = vec![vec![]];
+        for _ in coords {
+            grid = grid
+                .into_iter()
+                .flat_map(|g| {
+                    (-half_width..=half_width).map(move |s| {
+                        let mut g = g.clone();
+                        g.push(s);
+                        g
+                    })
+                })
+                .collect();
+        }
+
+        let points = grid
+            .into_iter()
+            .map(|steps| {
+                let mut disp = vec![0; ncoord];
+                for (&c, &s) in coords.iter().zip(&steps) {
+                    disp[c] = s;
+                }
+                let full: Vec<_> =
+                    disp.iter().map(|&s| s as f64 * step_size).collect();
+                CutPoint {
+                    x: steps.iter().map(|&s| s as f64 * step_size).collect(),
+                    fitted: fitted.map(|f| f.energy(&full)),
+                    computed: energies
+                        .and_then(|e| disps.position(&disp).map(|i| e[i])),
+                }
+            })
+            .collect();
+
+        Self {
+            coords: coords.to_vec(),
+            points,
+        }
+    }
+
+    /// write `self` to `w` in `format`
+    pub fn write(
+        &self,
+        mut w: impl Write,
+        format: Format,
+    ) -> std::io::Result<()> {
+        let names: Vec<_> =
+            self.coords.iter().map(|c| format!("S{}", c + 1)).collect();
+        let fmt = |v: Option<f64>, missing: &str| {
+            v.map_or(missing.to_owned(), |v| format!("{v:.12}"))
+        };
+        match format {
+            Format::Csv => {
+                writeln!(w, "{},fitted,computed", names.join(","))?;
+                for p in &self.points {
+                    let x: Vec<_> =
+                        p.x.iter().map(|x| format!("{x:.6}")).collect();
+                    writeln!(
+                        w,
+                        "{},{},{}",
+                        x.join(","),
+                        fmt(p.fitted, ""),
+                        fmt(p.computed, "")
+                    )?;
+                }
+            }
+            Format::Gnuplot => {
+                writeln!(w, "# {} fitted computed", names.join(" "))?;
+                for (i, p) in self.points.iter().enumerate() {
+                    // blank line between the rows of a 2D grid
+                    if self.coords.len() == 2
+                        && i > 0
+                        && p.x[0] != self.points[i - 1].x[0]
+                    {
+                        writeln!(w)?;
+                    }
+                    for x in &p.x {
+                        write!(w, "{x:12.6} ")?;
+                    }
+                    writeln!(
+                        w,
+                        "{:>20} {:>20}",
+                        fmt(p.fitted, "?"),
+                        fmt(p.computed, "?")
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
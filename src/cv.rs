@@ -0,0 +1,171 @@
+//! cross-validation of Taylor series fits for choosing the truncation order
+//! and the terms to include in the expansion
+
+use std::fmt::Display;
+
+use nalgebra as na;
+use rust_anpass::Anpass;
+
+use crate::{
+    fit::{design_matrix, fit_energies, Fit, Weighting},
+    Checks, Disps, Taylor,
+};
+
+/// the quality of a fit of one set of polynomial terms to a fixed set of
+/// energies
+#[derive(Clone, Debug, PartialEq)]
+pub struct Score {
+    /// the number of terms in the polynomial
+    pub nterms: usize,
+
+    /// the rank of the design matrix. this is less than `nterms` when the
+    /// displacements can't determine every term
+    pub rank: usize,
+
+    /// the root-mean-square residual of the fit to all of the energies
+    pub rms: f64,
+
+    /// the root-mean-square leave-one-out prediction error from the PRESS
+    /// statistic. this is infinite when some energy can only be predicted by
+    /// including it in the fit
+    pub loo: f64,
+
+    /// the root-mean-square k-fold prediction error, if requested
+    pub kfold: Option<f64>,
+}
+
+/// errors from cross-validating an expansion
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// k-fold cross-validation with fewer than 2 folds
+    TooFewFolds(usize),
+    /// an expansion that can't be generated
+    Taylor(crate::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::TooFewFolds(k) => write!(
+                f,
+                "k-fold cross-validation requires at least 2 folds, got {k}"
+            ),
+            Error::Taylor(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Score {
+    /// score the polynomial terms of `taylor` by fitting them to `energies`
+    /// at `disps`, which may be a superset of the displacements `taylor`
+    /// itself requires, with the displacements weighted by `weighting`. if
+    /// `folds` is provided, also compute the k-fold error with that many
+    /// folds. returns an error if `folds` is less than 2
+    pub fn new(
+        taylor: &Taylor,
+        disps: &Disps,
+        energies: &[f64],
+        step_size: f64,
+        weighting: Weighting,
+        folds: Option<usize>,
+    ) -> Result<Self, Error> {
+        if let Some(k) = folds.filter(|&k| k < 2) {
+            return Err(Error::TooFewFolds(k));
+        }
+        let anpass = taylor.to_anpass(disps, energies, step_size);
+        let fit = Fit::weighted(&anpass, weighting);
+        let n = energies.len();
+        let mut press = 0.0;
+        for i in 0..n {
+            let h = fit.leverage[i];
+            if 1.0 - h < 1e-10 {
+                press = f64::INFINITY;
+                break;
+            }
+            press += (fit.residuals[i] / (1.0 - h)).powi(2);
+        }
+        Ok(Self {
+            nterms: taylor.forces.len(),
+            rank: fit.rank,
+            rms: fit.rms(),
+            loo: (press / n as f64).sqrt(),
+            kfold: folds.map(|k| kfold(&anpass, weighting, k)),
+        })
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:5} {:5} {:12.3e} {:12.3e}",
+            self.nterms, self.rank, self.rms, self.loo
+        )?;
+        if let Some(k) = self.kfold {
+            write!(f, " {k:12.3e}")?;
+        }
+        Ok(())
+    }
+}
+
+/// return the subset of `anpass` with the displacements at `rows`
+fn select(anpass: &Anpass, rows: &[usize]) -> Anpass {
+    Anpass {
+        disps: anpass.disps.select_rows(rows),
+        energies: anpass.energies.select_rows(rows),
+        exponents: anpass.exponents.clone(),
+        bias: anpass.bias.clone(),
+    }
+}
+
+/// the root-mean-square prediction error of `anpass` over `k` folds, each
+/// holding out every kth displacement. `k` must be at least 2
+fn kfold(anpass: &Anpass, weighting: Weighting, k: usize) -> f64 {
+    let n = anpass.energies.len();
+    let mut sum = 0.0;
+    for fold in 0..k {
+        let (test, train): (Vec<_>, Vec<_>) =
+            (0..n).partition(|i| i % k == fold);
+        if test.is_empty() {
+            continue;
+        }
+        let fit = Fit::weighted(&select(anpass, &train), weighting);
+        let test = select(anpass, &test);
+        let pred: na::DVector<f64> = design_matrix(&test) * fit.coeffs;
+        sum += (fit_energies(&test) - pred).norm_squared();
+    }
+    (sum / n as f64).sqrt()
+}
+
+/// score expansions of each truncation order in `orders` for `nsic`
+/// coordinates and the modular and equivalence `checks` against `energies` at
+/// `disps`, usually the displacements of the largest order, with the
+/// displacements weighted by `weighting`. returns the orders paired with their
+/// scores, or an error if `folds` is less than 2 or one of the expansions
+/// can't be generated
+#[allow(clippy::too_many_arguments)]
+pub fn select_order(
+    orders: &[usize],
+    nsic: usize,
+    checks: &(Option<Checks>, Option<Checks>),
+    disps: &Disps,
+    energies: &[f64],
+    step_size: f64,
+    weighting: Weighting,
+    folds: Option<usize>,
+) -> Result<Vec<(usize, Score)>, Error> {
+    orders
+        .iter()
+        .map(|&m| {
+            let taylor =
+                Taylor::try_new(m, nsic, checks.0.clone(), checks.1.clone())
+                    .map_err(Error::Taylor)?;
+            let score = Score::new(
+                &taylor, disps, energies, step_size, weighting, folds,
+            )?;
+            Ok((m, score))
+        })
+        .collect()
+}
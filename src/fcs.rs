@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+/// a single force constant in the format of the anpass fort.9903 file. the
+/// first four fields are the 1-based SIC indices, padded with zeros, and the
+/// last field is the value of the derivative
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fc(pub usize, pub usize, pub usize, pub usize, pub f64);
+
+impl Fc {
+    /// return the nonzero indices of `self`
+    pub fn indices(&self) -> Vec<usize> {
+        [self.0, self.1, self.2, self.3]
+            .into_iter()
+            .filter(|&i| i != 0)
+            .collect()
+    }
+
+    /// the derivative order of `self`
+    pub fn order(&self) -> usize {
+        self.indices().len()
+    }
+}
+
+/// load the force constants from a fort.9903 file written by anpass. lines
+/// that don't contain four indices and a value, like the header, are skipped,
+/// as is the energy entry with all-zero indices. returns an error if any of
+/// the force constants refers to a SIC past `nsic`
+pub fn load_9903(
+    filename: impl AsRef<Path>,
+    nsic: usize,
+) -> std::io::Result<Vec<Fc>> {
+    let contents = std::fs::read_to_string(filename)?;
+    let mut ret = Vec::new();
+    for line in contents.lines() {
+        let fields: Vec<_> = line.split_whitespace().collect();
+        if fields.len() != 5 {
+            continue;
+        }
+        let Ok(idx) = fields[..4]
+            .iter()
+            .map(|f| f.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            continue;
+        };
+        let Ok(val) = fields[4].parse::<f64>() else {
+            continue;
+        };
+        let fc = Fc(idx[0], idx[1], idx[2], idx[3], val);
+        if fc.order() > 0 {
+            ret.push(fc);
+        }
+    }
+    check_indices(&ret, nsic)?;
+    Ok(ret)
+}
+
+/// return an error if any of `fcs` refers to a SIC outside of 1..=`nsic`
+pub fn check_indices(fcs: &[Fc], nsic: usize) -> std::io::Result<()> {
+    for fc in fcs {
+        if let Some(i) = fc.indices().into_iter().find(|&i| i > nsic) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "force constant {} {} {} {} refers to SIC {i}, but there \
+                     are only {nsic} SICs",
+                    fc.0, fc.1, fc.2, fc.3
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// return the symmetric `nsic` x `nsic` matrix of quadratic force constants
+/// from `fcs`, ignoring the higher-order entries
+pub fn quadratic(fcs: &[Fc], nsic: usize) -> na::DMatrix<f64> {
+    let mut ret = na::DMatrix::zeros(nsic, nsic);
+    for fc in fcs.iter().filter(|fc| fc.order() == 2) {
+        let (i, j) = (fc.0 - 1, fc.1 - 1);
+        ret[(i, j)] = fc.4;
+        ret[(j, i)] = fc.4;
+    }
+    ret
+}
@@ -0,0 +1,363 @@
+//! least-squares fitting of the Taylor series polynomial described by an
+//! [Anpass] and diagnostics for finding displacements whose energies are
+//! inconsistent with the fit
+
+use nalgebra as na;
+use rust_anpass::Anpass;
+use serde::{Deserialize, Serialize};
+
+use crate::{fcs::Fc, Disps, ForceMatrix};
+
+/// the design matrix for the polynomial in `anpass`, with a row for each
+/// displacement and a column for each term. the displacements are shifted by
+/// the bias, if there is one
+pub fn design_matrix(anpass: &Anpass) -> na::DMatrix<f64> {
+    let (ndisp, ncoord) = anpass.disps.shape();
+    let nterm = anpass.exponents.ncols();
+    let mut ret = na::DMatrix::from_element(ndisp, nterm, 1.0);
+    for i in 0..ndisp {
+        for j in 0..nterm {
+            for k in 0..ncoord {
+                let mut x = anpass.disps[(i, k)];
+                if let Some(bias) = &anpass.bias {
+                    x -= bias.disp[k];
+                }
+                ret[(i, j)] *= x.powi(anpass.exponents[(k, j)]);
+            }
+        }
+    }
+    ret
+}
+
+/// the energies in `anpass`, shifted by the bias, if there is one
+pub(crate) fn fit_energies(anpass: &Anpass) -> na::DVector<f64> {
+    match &anpass.bias {
+        Some(bias) => anpass.energies.add_scalar(-bias.energy),
+        None => anpass.energies.clone(),
+    }
+}
+
+/// the scheme for weighting the displacements in a least-squares fit
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Weighting {
+    /// every displacement has the same weight
+    #[default]
+    Equal,
+
+    /// weight each displacement by exp(-ΔE / kT), where ΔE is its energy
+    /// above the lowest energy and the field is kT in the units of the
+    /// energies. kT must be positive and finite
+    Boltzmann(f64),
+
+    /// weight each displacement by 1 / (1 + (r / scale)²), where r is the norm
+    /// of the displacement and the field is the scale in the units of the
+    /// displacements. the scale must be positive and finite
+    Distance(f64),
+}
+
+impl Weighting {
+    /// return the weight of each displacement in `anpass`
+    pub fn weights(&self, anpass: &Anpass) -> na::DVector<f64> {
+        let n = anpass.energies.len();
+        match *self {
+            Weighting::Equal => na::DVector::from_element(n, 1.0),
+            Weighting::Boltzmann(kt) => {
+                let emin = anpass.energies.min();
+                anpass.energies.map(|e| (-(e - emin) / kt).exp())
+            }
+            Weighting::Distance(scale) => na::DVector::from_fn(n, |i, _| {
+                let mut r = anpass.disps.row(i).into_owned();
+                if let Some(bias) = &anpass.bias {
+                    r -= bias.disp.transpose();
+                }
+                1.0 / (1.0 + (r.norm() / scale).powi(2))
+            }),
+        }
+    }
+}
+
+/// the result of a least-squares fit along with the per-displacement
+/// diagnostics
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fit {
+    /// the fitted coefficient of each term in the polynomial
+    pub coeffs: na::DVector<f64>,
+
+    /// the observed minus the fitted energy at each displacement
+    pub residuals: na::DVector<f64>,
+
+    /// the diagonal of the hat matrix, measuring how strongly each
+    /// displacement determines its own fitted value
+    pub leverage: na::DVector<f64>,
+
+    /// the internally studentized residuals
+    pub studentized: na::DVector<f64>,
+
+    /// Cook's distance for each displacement, the change in the fitted
+    /// energies caused by leaving it out of the fit
+    pub cooks: na::DVector<f64>,
+
+    /// the numerical rank of the design matrix
+    pub rank: usize,
+}
+
+impl Fit {
+    /// fit the polynomial in `anpass` to its energies by linear least squares
+    pub fn new(anpass: &Anpass) -> Self {
+        Self::weighted(anpass, Weighting::Equal)
+    }
+
+    /// fit the polynomial in `anpass` to its energies by weighted linear least
+    /// squares, with the weight of each displacement given by `weighting`.
+    /// the residuals are reported unweighted, but the other diagnostics
+    /// account for the weights
+    pub fn weighted(anpass: &Anpass, weighting: Weighting) -> Self {
+        let a = design_matrix(anpass);
+        let y = fit_energies(anpass);
+        let sqrt_w = weighting.weights(anpass).map(f64::sqrt);
+        let (n, p) = a.shape();
+        let mut aw = a.clone();
+        for (i, mut row) in aw.row_iter_mut().enumerate() {
+            row *= sqrt_w[i];
+        }
+        let yw = y.component_mul(&sqrt_w);
+        let svd = aw.svd(true, true);
+        let smax = svd.singular_values.max();
+        let tol = smax * n.max(p) as f64 * f64::EPSILON;
+        let coeffs = svd.solve(&yw, tol).unwrap();
+        let rank = svd.rank(tol);
+
+        let u = svd.u.unwrap();
+        let mut leverage = na::DVector::zeros(n);
+        for (k, s) in svd.singular_values.iter().enumerate() {
+            if *s > tol {
+                leverage += u.column(k).component_mul(&u.column(k));
+            }
+        }
+
+        let residuals = &y - a * &coeffs;
+        let rw = residuals.component_mul(&sqrt_w);
+        let dof = n.saturating_sub(rank);
+        // don't let rounding error in an otherwise exact fit look like
+        // significant residuals
+        let noise = 1e3 * f64::EPSILON * yw.amax();
+        let s2 = if dof > 0 {
+            (rw.norm_squared() / dof as f64).max(noise * noise)
+        } else {
+            0.0
+        };
+        let mut studentized = na::DVector::zeros(n);
+        let mut cooks = na::DVector::zeros(n);
+        for i in 0..n {
+            let h = leverage[i];
+            // points determined entirely by their own energy carry no
+            // information about their consistency with the others
+            if s2 == 0.0 || 1.0 - h < 1e-10 {
+                continue;
+            }
+            let r = rw[i];
+            studentized[i] = r / (s2 * (1.0 - h)).sqrt();
+            cooks[i] = r * r / (rank as f64 * s2) * h / (1.0 - h).powi(2);
+        }
+        Self {
+            coeffs,
+            residuals,
+            leverage,
+            studentized,
+            cooks,
+            rank,
+        }
+    }
+
+    /// the root-mean-square residual of the fit
+    pub fn rms(&self) -> f64 {
+        (self.residuals.norm_squared() / self.residuals.len() as f64).sqrt()
+    }
+
+    /// return the indices of the displacements whose studentized residual
+    /// exceeds `threshold` in magnitude or whose Cook's distance exceeds 1.
+    /// these are the energies that should be checked and likely recomputed
+    pub fn outliers(&self, threshold: f64) -> Vec<usize> {
+        (0..self.residuals.len())
+            .filter(|&i| {
+                self.studentized[i].abs() > threshold || self.cooks[i] > 1.0
+            })
+            .collect()
+    }
+
+    /// return the entries of `disps`, the displacements used to build the
+    /// fit, flagged by [Fit::outliers] with `threshold`
+    pub fn flagged(&self, disps: &Disps, threshold: f64) -> Disps {
+        let outliers = self.outliers(threshold);
+        let mut i = 0;
+        Disps(disps.0.filter(|_| {
+            i += 1;
+            outliers.binary_search(&(i - 1)).is_ok()
+        }))
+    }
+
+    /// convert the fitted coefficients of the polynomial terms in `forces`,
+    /// the rows used to build the fit, to force constants in the format of
+    /// the anpass fort.9903 file. the constant term and any terms beyond
+    /// fourth order are skipped
+    pub fn force_constants(&self, forces: &ForceMatrix) -> Vec<Fc> {
+        let mut ret = Vec::new();
+        for (row, coeff) in forces.iter().zip(&self.coeffs) {
+            let mut idx = Vec::new();
+            let mut fact = 1.0;
+            // descending order of the SICs, as in fort.9903
+            for (i, &e) in row.iter().enumerate().rev() {
+                for k in 1..=e {
+                    idx.push(i + 1);
+                    fact *= k as f64;
+                }
+            }
+            if idx.is_empty() || idx.len() > 4 {
+                continue;
+            }
+            idx.resize(4, 0);
+            ret.push(Fc(idx[0], idx[1], idx[2], idx[3], coeff * fact));
+        }
+        ret
+    }
+
+    /// pair the fitted coefficients with the polynomial terms in `forces`,
+    /// the rows used to build the fit, for evaluating the fitted surface
+    pub fn fitted(&self, forces: &ForceMatrix) -> FittedTaylor {
+        FittedTaylor::new(forces.clone(), self.coeffs.iter().copied().collect())
+    }
+}
+
+/// the value at `x` of the monomial with exponents `row`, differentiated once
+/// with respect to each coordinate in `derivs`
+fn eval_term(row: &[u8], x: &[f64], derivs: &[usize]) -> f64 {
+    let mut ret = 1.0;
+    for (k, (&e, &xk)) in row.iter().zip(x).enumerate() {
+        let mut e = e as i32;
+        for _ in derivs.iter().filter(|&&l| l == k) {
+            if e == 0 {
+                return 0.0;
+            }
+            ret *= e as f64;
+            e -= 1;
+        }
+        if e > 0 {
+            ret *= xk.powi(e);
+        }
+    }
+    ret
+}
+
+/// a Taylor series polynomial with fitted coefficients. the polynomial is a
+/// function of the SIC displacements from the reference geometry, in
+/// Ångstrom and radians rather than units of the step size
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FittedTaylor {
+    /// the exponents of each term, one row per term
+    pub forces: ForceMatrix,
+    /// the coefficient of each term
+    pub coeffs: Vec<f64>,
+}
+
+impl FittedTaylor {
+    /// construct a [FittedTaylor] from the exponents of its terms and their
+    /// coefficients. panics if the lengths don't match
+    pub fn new(forces: ForceMatrix, coeffs: Vec<f64>) -> Self {
+        assert_eq!(
+            forces.len(),
+            coeffs.len(),
+            "mismatch between the number of terms and coefficients"
+        );
+        Self { forces, coeffs }
+    }
+
+    /// construct the quartic [FittedTaylor] in `nsic` coordinates
+    /// corresponding to the force constants in `fcs`, as loaded from a
+    /// fort.9903 file. the energy at the reference is taken as zero
+    pub fn from_fcs(fcs: &[Fc], nsic: usize) -> Self {
+        let mut forces = ForceMatrix::new(nsic);
+        let mut coeffs = Vec::new();
+        for fc in fcs {
+            let mut row = vec![0u8; nsic];
+            for i in fc.indices() {
+                row[i - 1] += 1;
+            }
+            let fact: f64 = row
+                .iter()
+                .map(|&e| (1..=e as u32).product::<u32>() as f64)
+                .product();
+            forces.push(&row);
+            coeffs.push(fc.4 / fact);
+        }
+        Self { forces, coeffs }
+    }
+
+    /// the number of coordinates
+    pub fn ncoords(&self) -> usize {
+        self.forces.ncols()
+    }
+
+    /// evaluate the energy at the displacement `x`
+    pub fn energy(&self, x: &[f64]) -> f64 {
+        self.forces
+            .iter()
+            .zip(&self.coeffs)
+            .map(|(row, c)| c * eval_term(row, x, &[]))
+            .sum()
+    }
+
+    /// evaluate the gradient at the displacement `x`
+    pub fn gradient(&self, x: &[f64]) -> na::DVector<f64> {
+        let n = self.ncoords();
+        na::DVector::from_fn(n, |i, _| {
+            self.forces
+                .iter()
+                .zip(&self.coeffs)
+                .map(|(row, c)| c * eval_term(row, x, &[i]))
+                .sum()
+        })
+    }
+
+    /// evaluate the Hessian at the displacement `x`
+    pub fn hessian(&self, x: &[f64]) -> na::DMatrix<f64> {
+        let n = self.ncoords();
+        let mut ret = na::DMatrix::zeros(n, n);
+        for i in 0..n {
+            for j in 0..=i {
+                let h: f64 = self
+                    .forces
+                    .iter()
+                    .zip(&self.coeffs)
+                    .map(|(row, c)| c * eval_term(row, x, &[i, j]))
+                    .sum();
+                ret[(i, j)] = h;
+                ret[(j, i)] = h;
+            }
+        }
+        ret
+    }
+
+    /// search for a stationary point of the surface with Newton's method
+    /// starting from `guess`. returns the stationary point once the norm of
+    /// the gradient falls below `tol`, or None if that doesn't happen within
+    /// `maxit` iterations. whether the point is a minimum or a transition
+    /// state can be determined from the eigenvalues of the [Self::hessian]
+    /// there
+    pub fn stationary_point(
+        &self,
+        guess: &[f64],
+        tol: f64,
+        maxit: usize,
+    ) -> Option<na::DVector<f64>> {
+        let mut x = na::DVector::from_column_slice(guess);
+        for _ in 0..maxit {
+            let g = self.gradient(x.as_slice());
+            if g.norm() < tol {
+                return Some(x);
+            }
+            let step = self.hessian(x.as_slice()).lu().solve(&g)?;
+            x -= step;
+        }
+        None
+    }
+}
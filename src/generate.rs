@@ -0,0 +1,404 @@
+//! automatic generation of symmetry internal coordinates from the
+//! connectivity of a molecule. a redundant set of simple internals is built
+//! from the bonds, projected onto the irreps of the largest abelian point group
+//! whose symmetry elements lie along the Cartesian axes, and pruned to a
+//! non-redundant set using the rank of the B-matrix
+
+use intder::Siic;
+use nalgebra as na;
+
+use crate::internals::{b_row, is_linear, value, Unsupported};
+
+type Vec3 = na::Vector3<f64>;
+
+/// errors from generating symmetry internal coordinates
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// no covalent radius is available for the element with this symbol
+    NoRadius(String),
+    /// a simple internal can't be mapped by the symmetry operations
+    Unsupported(Unsupported),
+    /// the image of a simple internal under a symmetry operation is not among
+    /// the generated simple internals. the fields are the simple internal and
+    /// its image
+    MissingImage(Siic, Siic),
+    /// the dummy atom with this 0-based index is not on a symmetry axis
+    DummyOffAxis(usize),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NoRadius(label) => {
+                write!(f, "no covalent radius available for atom {label}")
+            }
+            Error::Unsupported(u) => write!(f, "{u}"),
+            Error::MissingImage(siic, img) => {
+                write!(f, "image {img} of {siic} not found")
+            }
+            Error::DummyOffAxis(d) => {
+                write!(f, "dummy atom {} is not on a symmetry axis", d + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// bends larger than this, in degrees, are treated as linear
+const LINEAR_BEND: f64 = 175.0;
+
+/// return the covalent radius in Ångstrom of the element with symbol `label`,
+/// from B. Cordero et al., Dalton Trans., 2008, 2832-2838, or None for
+/// elements past Ar
+pub fn covalent_radius(label: &str) -> Option<f64> {
+    let r = match label {
+        "H" => 0.31,
+        "He" => 0.28,
+        "Li" => 1.28,
+        "Be" => 0.96,
+        "B" => 0.84,
+        "C" => 0.76,
+        "N" => 0.71,
+        "O" => 0.66,
+        "F" => 0.57,
+        "Ne" => 0.58,
+        "Na" => 1.66,
+        "Mg" => 1.41,
+        "Al" => 1.21,
+        "Si" => 1.11,
+        "P" => 1.07,
+        "S" => 1.05,
+        "Cl" => 1.02,
+        "Ar" => 1.06,
+        _ => return None,
+    };
+    Some(r)
+}
+
+/// return the pairs of atoms in `geom`, in Ångstrom, that are bonded, taken
+/// as those closer than 1.3 times the sum of their covalent radii
+pub fn bonds(
+    labels: &[String],
+    geom: &[Vec3],
+) -> Result<Vec<(usize, usize)>, Error> {
+    let radii = labels
+        .iter()
+        .map(|l| covalent_radius(l).ok_or_else(|| Error::NoRadius(l.clone())))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut ret = Vec::new();
+    for i in 0..labels.len() {
+        for j in i + 1..labels.len() {
+            let cutoff = 1.3 * (radii[i] + radii[j]);
+            if (geom[i] - geom[j]).norm() < cutoff {
+                ret.push((i, j));
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// return `siic` in a canonical atom ordering, so that equivalent coordinates
+/// compare equal
+fn canonical(siic: Siic) -> Siic {
+    match siic {
+        Siic::Stretch(a, b) if a > b => Siic::Stretch(b, a),
+        Siic::Bend(a, b, c) if a > c => Siic::Bend(c, b, a),
+        Siic::Torsion(a, b, c, d) if (a, b) > (d, c) => {
+            Siic::Torsion(d, c, b, a)
+        }
+        Siic::Lin1(a, b, c, d) if a > c => Siic::Lin1(c, b, a, d),
+        s => s,
+    }
+}
+
+/// return a redundant set of simple internals for the first `natoms` atoms
+/// of `geom`, in Ångstrom, from the bonds between them: stretches for the
+/// bonds, bends for pairs of bonds sharing an atom, and torsions for chains of
+/// three bonds. atoms with three or more bonds and no torsions through them
+/// also get improper torsions to describe their out-of-plane motion. nearly
+/// linear bends are replaced by pairs of LIN1 coordinates against the two
+/// atoms following the real atoms in `geom`, which should be dummy atoms
+/// perpendicular to the linear fragment, or skipped if there aren't any
+pub fn simple_internals(
+    labels: &[String],
+    geom: &[Vec3],
+    natoms: usize,
+) -> Result<Vec<Siic>, Error> {
+    let bonds = bonds(labels, geom)?;
+    let mut neighbors = vec![Vec::new(); natoms];
+    for &(i, j) in &bonds {
+        neighbors[i].push(j);
+        neighbors[j].push(i);
+    }
+    // bends are always supported, so value can't fail
+    let is_linear_bend = |a, b, c| {
+        value(&Siic::Bend(a, b, c), geom)
+            .is_ok_and(|v| v.to_degrees() > LINEAR_BEND)
+    };
+
+    let mut ret = Vec::new();
+    let mut push = |siic| {
+        let siic = canonical(siic);
+        if !ret.contains(&siic) {
+            ret.push(siic);
+        }
+    };
+    for &(i, j) in &bonds {
+        push(Siic::Stretch(i, j));
+    }
+    for (b, nb) in neighbors.iter().enumerate() {
+        for (k, &a) in nb.iter().enumerate() {
+            for &c in &nb[k + 1..] {
+                if !is_linear_bend(a, b, c) {
+                    push(Siic::Bend(a, b, c));
+                } else if geom.len() >= natoms + 2 {
+                    push(Siic::Lin1(a, b, c, natoms));
+                    push(Siic::Lin1(a, b, c, natoms + 1));
+                }
+            }
+        }
+    }
+    let mut twisted = vec![false; natoms];
+    for &(b, c) in &bonds {
+        for &a in neighbors[b].iter().filter(|&&a| a != c) {
+            for &d in neighbors[c].iter().filter(|&&d| d != b && d != a) {
+                if is_linear_bend(a, b, c) || is_linear_bend(b, c, d) {
+                    continue;
+                }
+                push(Siic::Torsion(a, b, c, d));
+                twisted[b] = true;
+                twisted[c] = true;
+            }
+        }
+    }
+    for b in (0..natoms).filter(|&b| !twisted[b] && neighbors[b].len() >= 3) {
+        let n = &neighbors[b];
+        for i in 0..n.len() {
+            for j in 0..n.len() {
+                for k in 0..n.len() {
+                    if i == j
+                        || i == k
+                        || j == k
+                        || is_linear_bend(n[i], b, n[j])
+                    {
+                        continue;
+                    }
+                    push(Siic::Torsion(n[i], b, n[j], n[k]));
+                }
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// a symmetry operation represented by the signs it applies to the x, y, and
+/// z coordinates, along with the image of each atom
+struct Operation {
+    signs: Vec3,
+    perm: Vec<usize>,
+}
+
+impl Operation {
+    /// the determinant of the operation, -1 for improper operations
+    fn det(&self) -> f64 {
+        self.signs.iter().product()
+    }
+}
+
+/// return the operations among the eight axis-aligned rotations,
+/// reflections, and inversion that map `geom` onto itself within `eps`,
+/// without exchanging atoms with different `labels`
+fn operations(labels: &[String], geom: &[Vec3], eps: f64) -> Vec<Operation> {
+    let mut ret = Vec::new();
+    for bits in 0..8 {
+        let signs =
+            Vec3::from_fn(|i, _| if bits >> i & 1 == 1 { -1.0 } else { 1.0 });
+        let perm: Option<Vec<usize>> = geom
+            .iter()
+            .enumerate()
+            .map(|(i, g)| {
+                let image = g.component_mul(&signs);
+                geom.iter().enumerate().position(|(j, h)| {
+                    labels.get(i) == labels.get(j) && (image - h).norm() < eps
+                })
+            })
+            .collect();
+        if let Some(perm) = perm {
+            ret.push(Operation { signs, perm });
+        }
+    }
+    ret
+}
+
+/// return the image of `siic` under `op` as its index in `siics` and the sign
+/// relating the two. returns an error if the image is not in `siics`
+fn image(
+    siic: &Siic,
+    op: &Operation,
+    siics: &[Siic],
+) -> Result<(usize, f64), Error> {
+    let p = &op.perm;
+    let (img, sign) = match *siic {
+        Siic::Stretch(a, b) => (Siic::Stretch(p[a], p[b]), 1.0),
+        Siic::Bend(a, b, c) => (Siic::Bend(p[a], p[b], p[c]), 1.0),
+        Siic::Torsion(a, b, c, d) => {
+            (Siic::Torsion(p[a], p[b], p[c], p[d]), op.det())
+        }
+        // the dummy atom stays in place, and the caller accounts for the
+        // operation reversing its direction
+        Siic::Lin1(a, b, c, d) => (Siic::Lin1(p[a], p[b], p[c], d), op.det()),
+        _ => return Err(Error::Unsupported(Unsupported(siic.clone()))),
+    };
+    // swapping the end atoms of a linear bend flips its sign
+    let sign = match (&img, canonical(img.clone())) {
+        (Siic::Lin1(a, ..), Siic::Lin1(ca, ..)) if *a != ca => -sign,
+        _ => sign,
+    };
+    let img = canonical(img);
+    let idx = siics
+        .iter()
+        .position(|s| *s == img)
+        .ok_or_else(|| Error::MissingImage(siic.clone(), img))?;
+    Ok((idx, sign))
+}
+
+/// return the sign by which `op` maps the direction of dummy atom `d` in
+/// `geom` onto itself, or None if it maps it elsewhere
+fn dummy_sign(op: &Operation, geom: &[Vec3], d: usize) -> Option<f64> {
+    let g = geom[d];
+    let image = g.component_mul(&op.signs);
+    if (image - g).norm() < 1e-8 {
+        Some(1.0)
+    } else if (image + g).norm() < 1e-8 {
+        Some(-1.0)
+    } else {
+        None
+    }
+}
+
+/// generate non-redundant symmetry internal coordinates for the first
+/// `natoms` atoms of `geom`, in Ångstrom in a normalized orientation, with any
+/// dummy atoms following them. `eps` is the tolerance for identifying
+/// symmetry-equivalent atoms. returns the simple internals and the SICs built
+/// from them, grouped by irrep. the SICs span 3N-6 coordinates, or 3N-5 for
+/// linear molecules, unless the connectivity doesn't provide enough simple
+/// internals. only the axis-aligned symmetry operations are used, so the SICs
+/// are adapted to the largest abelian subgroup of the point group with its
+/// symmetry elements along the axes
+pub fn generate(
+    labels: &[String],
+    geom: &[Vec3],
+    natoms: usize,
+    eps: f64,
+) -> Result<(Vec<Siic>, Vec<Vec<f64>>), Error> {
+    let siics = simple_internals(labels, geom, natoms)?;
+    let ops = operations(labels, &geom[..natoms], eps);
+    let nsiic = siics.len();
+
+    // the representation of each operation over the simple internals
+    let reps = ops
+        .iter()
+        .map(|op| {
+            let mut d = na::DMatrix::zeros(nsiic, nsiic);
+            for (i, siic) in siics.iter().enumerate() {
+                let (j, mut sign) = image(siic, op, &siics)?;
+                if let Siic::Lin1(.., dummy) = siic {
+                    sign *= dummy_sign(op, geom, *dummy)
+                        .ok_or(Error::DummyOffAxis(*dummy))?;
+                }
+                d[(j, i)] = sign;
+            }
+            Ok(d)
+        })
+        .collect::<Result<Vec<na::DMatrix<f64>>, Error>>()?;
+
+    // the characters of the group, from restricting those of the full group
+    // of axis-aligned operations
+    let mut characters: Vec<Vec<f64>> = Vec::new();
+    for w in 0..8 {
+        let chi: Vec<f64> = ops
+            .iter()
+            .map(|op| {
+                (0..3)
+                    .filter(|i| w >> i & 1 == 1)
+                    .map(|i| op.signs[i])
+                    .product()
+            })
+            .collect();
+        if !characters.contains(&chi) {
+            characters.push(chi);
+        }
+    }
+
+    let b: Vec<_> = siics
+        .iter()
+        .map(|s| b_row(s, geom).map(|r| r.rows(0, 3 * natoms).transpose()))
+        .collect::<Result<_, _>>()
+        .map_err(Error::Unsupported)?;
+    let dof = match natoms {
+        1 => 0,
+        _ if is_linear(geom, natoms) => 3 * natoms - 5,
+        _ => 3 * natoms - 6,
+    };
+    let mut sics = Vec::new();
+    let mut rows: Vec<na::RowDVector<f64>> = Vec::new();
+    let mut rank = 0;
+    for chi in &characters {
+        let mut proj = na::DMatrix::zeros(nsiic, nsiic);
+        for (c, d) in chi.iter().zip(&reps) {
+            proj += *c * d;
+        }
+        proj /= ops.len() as f64;
+        for i in 0..nsiic {
+            if rank == dof {
+                break;
+            }
+            let v = proj.column(i);
+            let vmax = v.amax();
+            if vmax < 1e-8 {
+                continue;
+            }
+            let v = v / vmax;
+            let mut row = na::RowDVector::zeros(3 * natoms);
+            for (j, bj) in b.iter().enumerate() {
+                row += v[j] * bj;
+            }
+            rows.push(row);
+            let m = na::DMatrix::from_rows(&rows);
+            let svd = m.svd(false, false);
+            let r = svd
+                .singular_values
+                .iter()
+                .filter(|&&s| s > 1e-6 * svd.singular_values.max())
+                .count();
+            if r > rank {
+                rank = r;
+                sics.push(v.iter().map(|x| clean(*x)).collect::<Vec<_>>());
+            } else {
+                rows.pop();
+            }
+        }
+    }
+
+    // drop the simple internals not used by any of the SICs
+    let used: Vec<_> = (0..nsiic)
+        .filter(|&j| sics.iter().any(|s: &Vec<f64>| s[j] != 0.0))
+        .collect();
+    let siics = used.iter().map(|&j| siics[j].clone()).collect();
+    let sics = sics
+        .into_iter()
+        .map(|s| used.iter().map(|&j| s[j]).collect())
+        .collect();
+    Ok((siics, sics))
+}
+
+/// round `x` to zero or the nearest integer if it is within rounding error of
+/// one
+fn clean(x: f64) -> f64 {
+    if (x - x.round()).abs() < 1e-10 {
+        x.round()
+    } else {
+        x
+    }
+}
@@ -0,0 +1,167 @@
+//! harmonic frequencies from the quadratic SIC force constants using Wilson's
+//! GF method
+
+use std::fmt::Display;
+
+use intder::{Intder, Siic};
+use nalgebra as na;
+use symm::Irrep;
+
+use crate::internals::{geom_ang, sym_b_matrix, Unsupported};
+
+/// conversion factor from sqrt(hartree / (Å² amu)) to cm⁻¹
+const FREQ_CONV: f64 = 2720.22864939427;
+
+/// return the mass in amu of the most abundant isotope of the element with
+/// symbol `label`, or None if it is not available
+pub fn atomic_mass(label: &str) -> Option<f64> {
+    Some(match label {
+        "H" => 1.00782503223,
+        "He" => 4.00260325413,
+        "Li" => 7.0160034366,
+        "Be" => 9.012183065,
+        "B" => 11.00930536,
+        "C" => 12.0,
+        "N" => 14.00307400443,
+        "O" => 15.99491461957,
+        "F" => 18.99840316273,
+        "Ne" => 19.9924401762,
+        "Na" => 22.9897692820,
+        "Mg" => 23.985041697,
+        "Al" => 26.98153853,
+        "Si" => 27.97692653465,
+        "P" => 30.97376199842,
+        "S" => 31.9720711744,
+        "Cl" => 34.968852682,
+        "Ar" => 39.9623831237,
+        _ => return None,
+    })
+}
+
+/// errors from computing harmonic frequencies
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// no mass is available for the element with this symbol
+    NoMass(String),
+    /// a simple internal's B-matrix elements are not available
+    Unsupported(Unsupported),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NoMass(label) => {
+                write!(f, "no mass available for atom {label}")
+            }
+            Error::Unsupported(u) => write!(f, "{u}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// the harmonic frequencies in cm⁻¹ and their irreps, sorted in descending
+/// order of frequency. imaginary frequencies are reported as negative values
+#[derive(Clone, Debug, PartialEq)]
+pub struct Harmonic {
+    pub freqs: Vec<f64>,
+    pub irreps: Vec<Irrep>,
+}
+
+impl Harmonic {
+    /// compute the harmonic frequencies from the quadratic force constants
+    /// `fc2` in hartree/Å² for the SICs in `intder`, whose irreps are given by
+    /// `irreps`. any dummy atoms at the end of `intder.geom` are treated as
+    /// fixed points in space. returns an error if an atom has no known mass
+    /// or a simple internal is not supported
+    pub fn new(
+        intder: &Intder,
+        fc2: &na::DMatrix<f64>,
+        irreps: &[Irrep],
+    ) -> Result<Self, Error> {
+        let masses = intder
+            .atoms
+            .iter()
+            .map(|a| {
+                atomic_mass(&a.label)
+                    .ok_or_else(|| Error::NoMass(a.label.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_parts(
+            &intder.simple_internals,
+            &intder.symmetry_internals,
+            &geom_ang(intder),
+            &masses,
+            fc2,
+            irreps,
+        )
+        .map_err(Error::Unsupported)
+    }
+
+    /// like [Harmonic::new] but taking the individual pieces of an intder
+    /// input. `geom` is in Ångstrom and may contain trailing dummy atoms
+    /// without entries in `masses`
+    pub fn from_parts(
+        siics: &[Siic],
+        sics: &[Vec<f64>],
+        geom: &[na::Vector3<f64>],
+        masses: &[f64],
+        fc2: &na::DMatrix<f64>,
+        irreps: &[Irrep],
+    ) -> Result<Self, Unsupported> {
+        assert_eq!(sics.len(), irreps.len());
+        let ncart = 3 * masses.len();
+        let b = sym_b_matrix(siics, sics, geom)?
+            .columns(0, ncart)
+            .into_owned();
+        let minv = na::DVector::from_iterator(
+            ncart,
+            masses.iter().flat_map(|m| [1.0 / m; 3]),
+        );
+        let g = &b * na::DMatrix::from_diagonal(&minv) * b.transpose();
+
+        // the SICs are symmetry-adapted, so G and F are block diagonal by
+        // irrep, and diagonalizing each block separately labels the modes
+        let mut blocks: Vec<Irrep> = irreps.to_vec();
+        blocks.sort();
+        blocks.dedup();
+        let mut modes = Vec::new();
+        for irrep in blocks {
+            let idx: Vec<_> = irreps
+                .iter()
+                .enumerate()
+                .filter_map(|(i, ir)| (*ir == irrep).then_some(i))
+                .collect();
+            let gb = g.select_rows(&idx).select_columns(&idx);
+            let fb = fc2.select_rows(&idx).select_columns(&idx);
+            // symmetrize GF as G^½ F G^½ so the eigenvalues stay real
+            let eig = na::SymmetricEigen::new(gb);
+            let sqrt = eig.eigenvalues.map(|v| v.max(0.0).sqrt());
+            let gh = &eig.eigenvectors
+                * na::DMatrix::from_diagonal(&sqrt)
+                * eig.eigenvectors.transpose();
+            let gfg = &gh * fb * &gh;
+            for lambda in gfg.symmetric_eigenvalues().iter() {
+                modes.push((
+                    lambda.signum() * lambda.abs().sqrt() * FREQ_CONV,
+                    irrep,
+                ));
+            }
+        }
+        modes.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let (freqs, irreps) = modes.into_iter().unzip();
+        Ok(Self { freqs, irreps })
+    }
+}
+
+impl Display for Harmonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:>5}{:>12}{:>8}", "Mode", "Freq", "Irrep")?;
+        for (i, (freq, irrep)) in
+            self.freqs.iter().zip(&self.irreps).enumerate()
+        {
+            writeln!(f, "{:5}{:12.1}{:>8}", i + 1, freq, irrep)?;
+        }
+        Ok(())
+    }
+}
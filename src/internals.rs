@@ -0,0 +1,260 @@
+//! values and Cartesian derivatives (Wilson B-matrix elements) of the simple
+//! internal coordinates supported by intder
+
+use intder::{Intder, Siic};
+use nalgebra as na;
+
+type Vec3 = na::Vector3<f64>;
+
+/// conversion factor from bohr to Ångstrom
+pub const BOHR_TO_ANG: f64 = 0.529177210903;
+
+/// return the geometry from `intder`, including any dummy atoms, converted
+/// from bohr to Ångstrom
+pub fn geom_ang(intder: &Intder) -> Vec<Vec3> {
+    intder
+        .geom
+        .0
+        .iter()
+        .map(|g| Vec3::new(g[0], g[1], g[2]) * BOHR_TO_ANG)
+        .collect()
+}
+
+/// an error for a simple internal whose B-matrix elements are not available
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unsupported(pub Siic);
+
+impl std::fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is not supported yet", self.0)
+    }
+}
+
+impl std::error::Error for Unsupported {}
+
+/// return the value of `siic` at `geom`, in Ångstrom for stretches and radians
+/// for angles. torsions follow the usual convention of a positive angle for
+/// clockwise rotation of the first atom onto the last when viewed down the
+/// central bond. linear bends are measured relative to the direction of their
+/// last atom, usually a dummy atom, from the origin. returns an error if
+/// `siic` is not supported
+pub fn value(siic: &Siic, geom: &[Vec3]) -> Result<f64, Unsupported> {
+    Ok(match siic {
+        Siic::Stretch(a, b) => (geom[*b] - geom[*a]).norm(),
+        Siic::Bend(a, b, c) => {
+            let e1 = (geom[*a] - geom[*b]).normalize();
+            let e2 = (geom[*c] - geom[*b]).normalize();
+            e1.dot(&e2).clamp(-1.0, 1.0).acos()
+        }
+        Siic::Torsion(a, b, c, d) => {
+            let f = geom[*a] - geom[*b];
+            let g = geom[*b] - geom[*c];
+            let h = geom[*d] - geom[*c];
+            let a = f.cross(&g);
+            let b = h.cross(&g);
+            let y = b.cross(&a).dot(&g) / g.norm();
+            y.atan2(a.dot(&b))
+        }
+        Siic::Lin1(a, b, c, d) => {
+            let e21 = (geom[*a] - geom[*b]).normalize();
+            let e23 = (geom[*c] - geom[*b]).normalize();
+            let ed = geom[*d].normalize();
+            ed.dot(&e23.cross(&e21)).clamp(-1.0, 1.0).asin()
+        }
+        _ => return Err(Unsupported(siic.clone())),
+    })
+}
+
+/// return the row of the B-matrix for `siic` at `geom`. the row has length 3N,
+/// where N is the number of atoms in `geom`. returns an error if `siic` is not
+/// supported
+pub fn b_row(
+    siic: &Siic,
+    geom: &[Vec3],
+) -> Result<na::DVector<f64>, Unsupported> {
+    let mut ret = na::DVector::zeros(3 * geom.len());
+    let mut set = |atom: usize, v: Vec3| {
+        for k in 0..3 {
+            ret[3 * atom + k] += v[k];
+        }
+    };
+    match siic {
+        Siic::Stretch(a, b) => {
+            let e = (geom[*b] - geom[*a]).normalize();
+            set(*a, -e);
+            set(*b, e);
+        }
+        Siic::Bend(a, b, c) => {
+            let r1 = geom[*a] - geom[*b];
+            let r2 = geom[*c] - geom[*b];
+            let (l1, l2) = (r1.norm(), r2.norm());
+            let (e1, e2) = (r1 / l1, r2 / l2);
+            let cos = e1.dot(&e2);
+            let sin = (1.0 - cos * cos).sqrt();
+            let sa = (cos * e1 - e2) / (l1 * sin);
+            let sc = (cos * e2 - e1) / (l2 * sin);
+            set(*a, sa);
+            set(*c, sc);
+            set(*b, -sa - sc);
+        }
+        // Blondel and Karplus, J. Comput. Chem. 17, 1996, 1132-1141
+        Siic::Torsion(a, b, c, d) => {
+            let f = geom[*a] - geom[*b];
+            let g = geom[*b] - geom[*c];
+            let h = geom[*d] - geom[*c];
+            let va = f.cross(&g);
+            let vb = h.cross(&g);
+            let (a2, b2) = (va.norm_squared(), vb.norm_squared());
+            let gn = g.norm();
+            let fg = f.dot(&g) / (a2 * gn);
+            let hg = h.dot(&g) / (b2 * gn);
+            set(*a, -gn / a2 * va);
+            set(*b, gn / a2 * va + fg * va - hg * vb);
+            set(*c, -gn / b2 * vb - fg * va + hg * vb);
+            set(*d, gn / b2 * vb);
+        }
+        Siic::Lin1(a, b, c, d) => {
+            let r21 = geom[*a] - geom[*b];
+            let r23 = geom[*c] - geom[*b];
+            let (l21, l23, ld) = (r21.norm(), r23.norm(), geom[*d].norm());
+            let (e21, e23, ed) = (r21 / l21, r23 / l23, geom[*d] / ld);
+            // projection onto the plane perpendicular to e
+            let perp = |e: &Vec3, v: Vec3| v - e * e.dot(&v);
+            let w = e23.cross(&e21);
+            let cos = (1.0 - ed.dot(&w).powi(2)).sqrt();
+            let sa = perp(&e21, ed.cross(&e23)) / (l21 * cos);
+            let sc = perp(&e23, e21.cross(&ed)) / (l23 * cos);
+            set(*a, sa);
+            set(*c, sc);
+            set(*b, -sa - sc);
+            set(*d, perp(&ed, w) / (ld * cos));
+        }
+        _ => return Err(Unsupported(siic.clone())),
+    }
+    Ok(ret)
+}
+
+/// return the B-matrix for the simple internals in `siics` at `geom`, or an
+/// error for the first one that is not supported
+pub fn b_matrix(
+    siics: &[Siic],
+    geom: &[Vec3],
+) -> Result<na::DMatrix<f64>, Unsupported> {
+    let mut ret = na::DMatrix::zeros(siics.len(), 3 * geom.len());
+    for (i, siic) in siics.iter().enumerate() {
+        ret.set_row(i, &b_row(siic, geom)?.transpose());
+    }
+    Ok(ret)
+}
+
+/// return the matrix transforming simple internals to the symmetry internals
+/// in `sics`. like intder, each row of `sics` is normalized first
+pub fn u_matrix(sics: &[Vec<f64>], nsiic: usize) -> na::DMatrix<f64> {
+    let mut ret = na::DMatrix::zeros(sics.len(), nsiic);
+    for (i, sic) in sics.iter().enumerate() {
+        let norm = sic.iter().map(|s| s * s).sum::<f64>().sqrt();
+        for (j, s) in sic.iter().enumerate() {
+            ret[(i, j)] = s / norm;
+        }
+    }
+    ret
+}
+
+/// return the B-matrix for the symmetry internals `sics`, built from the simple
+/// internals `siics`, at `geom`, or an error if any of `siics` is not
+/// supported
+pub fn sym_b_matrix(
+    siics: &[Siic],
+    sics: &[Vec<f64>],
+    geom: &[Vec3],
+) -> Result<na::DMatrix<f64>, Unsupported> {
+    Ok(u_matrix(sics, siics.len()) * b_matrix(siics, geom)?)
+}
+
+/// return whether the first `natoms` atoms of `geom` lie on a line
+pub fn is_linear(geom: &[Vec3], natoms: usize) -> bool {
+    let Some(axis) = geom[..natoms]
+        .iter()
+        .map(|g| g - geom[0])
+        .find(|v| v.norm() > 1e-6)
+    else {
+        return true;
+    };
+    let axis = axis.normalize();
+    geom[..natoms]
+        .iter()
+        .all(|g| (g - geom[0]).cross(&axis).norm() < 1e-6)
+}
+
+/// a problem with a set of SICs found by [check_sics]
+#[derive(Clone, Debug, PartialEq)]
+pub enum SicProblem {
+    /// the SICs span fewer internal degrees of freedom than the molecule has.
+    /// the fields are the rank of the SIC B-matrix and the number of degrees
+    /// of freedom
+    Incomplete(usize, usize),
+
+    /// the SIC with this 0-based index is a linear combination of the SICs
+    /// before it
+    Redundant(usize),
+}
+
+impl std::fmt::Display for SicProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SicProblem::Incomplete(rank, dof) => write!(
+                f,
+                "the SICs span only {rank} of {dof} internal degrees of \
+                 freedom, {} coordinates are missing",
+                dof - rank
+            ),
+            SicProblem::Redundant(i) => {
+                write!(f, "SIC {} is redundant with the SICs before it", i + 1)
+            }
+        }
+    }
+}
+
+/// the numerical rank of `m`
+fn rank(m: na::DMatrix<f64>) -> usize {
+    let svd = m.svd(false, false);
+    let tol = 1e-6 * svd.singular_values.max().max(1.0);
+    svd.singular_values.iter().filter(|&&s| s > tol).count()
+}
+
+/// check that the SICs `sics`, built from the simple internals `siics`, form
+/// a complete, non-redundant set of internal coordinates at `geom`, in
+/// Ångstrom, where the first `natoms` atoms are real and the rest are dummy
+/// atoms. a complete set has rank 3N-6, or 3N-5 for a linear molecule. returns
+/// an empty vector if there are no problems, or an error if any of `siics` is
+/// not supported
+pub fn check_sics(
+    siics: &[Siic],
+    sics: &[Vec<f64>],
+    geom: &[Vec3],
+    natoms: usize,
+) -> Result<Vec<SicProblem>, Unsupported> {
+    let b = sym_b_matrix(siics, sics, geom)?
+        .columns(0, 3 * natoms)
+        .into_owned();
+    let mut ret = Vec::new();
+    let mut prev = 0;
+    for i in 0..b.nrows() {
+        let r = rank(b.rows(0, i + 1).into_owned());
+        if r == prev {
+            ret.push(SicProblem::Redundant(i));
+        }
+        prev = r;
+    }
+    let dof = if natoms == 1 {
+        0
+    } else if is_linear(geom, natoms) {
+        3 * natoms - 5
+    } else {
+        3 * natoms - 6
+    };
+    if prev < dof {
+        ret.push(SicProblem::Incomplete(prev, dof));
+    }
+    Ok(ret)
+}
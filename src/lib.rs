@@ -1,28 +1,178 @@
 // could I use const generics for these for m and n?
+use std::{collections::HashMap, path::Path};
+
 use nalgebra as na;
+use rayon::prelude::*;
 use rust_anpass::Anpass;
 use serde::{Deserialize, Serialize};
 use symm::{Irrep, PointGroup};
 
+use census::Census;
 pub use checks::*;
+pub mod cache;
+pub mod census;
 pub mod checks;
+pub mod consistency;
+pub mod cuts;
+pub mod cv;
+pub mod fcs;
+pub mod fit;
+pub mod generate;
+pub mod harm;
+pub mod internals;
+pub mod ordering;
+pub mod project;
+pub mod rows;
+pub mod spectro;
+pub mod transform;
+
+pub use rows::{ForceMatrix, Rows};
 
 #[cfg(test)]
 mod tests;
 
+/// the largest supported truncation order. the displacements for an
+/// expansion of order m step up to m - 1 times in each direction, which must
+/// fit in an i8
+pub const MAX_ORDER: usize = i8::MAX as usize + 1;
+
+/// errors from generating a [Taylor] series expansion
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// the truncation order is larger than [MAX_ORDER]
+    OrderTooLarge(usize),
+    /// the number of rows in the expansion overflows a usize
+    TooManyRows { m: usize, n: usize },
+    /// a check refers to a coordinate outside of 1..=n
+    InvalidCheck { check: usize, n: usize },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::OrderTooLarge(m) => write!(
+                f,
+                "truncation order {m} is larger than the maximum of {MAX_ORDER}"
+            ),
+            Error::TooManyRows { m, n } => write!(
+                f,
+                "an expansion of order {m} in {n} coordinates has too many \
+                 rows to generate"
+            ),
+            Error::InvalidCheck { check, n } => write!(
+                f,
+                "check on coordinate {check} is out of range for {n} \
+                 coordinates"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// errors from assembling energies with [Disps::merge_energies]
+#[derive(Clone, Debug, PartialEq)]
+pub enum MergeError {
+    /// the part with this 0-based index has a different number of
+    /// displacements and energies
+    LengthMismatch {
+        part: usize,
+        disps: usize,
+        energies: usize,
+    },
+    /// none of the parts has an energy for this displacement
+    Missing(Vec<i8>),
+}
+
+impl std::fmt::Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeError::LengthMismatch {
+                part,
+                disps,
+                energies,
+            } => write!(
+                f,
+                "part {} has {energies} energies for {disps} displacements",
+                part + 1
+            ),
+            MergeError::Missing(d) => {
+                write!(f, "no energy found for displacement {d:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
 /// a taylor series expansion of f(x1, x2, ... n) of order m-1
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Taylor {
-    pub forces: Vec<Vec<u8>>,
+    pub forces: ForceMatrix,
 }
 
+/// the sorted, unique displacements for a [Taylor] series expansion, in units
+/// of the step size
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct Disps(Vec<Vec<i8>>);
+pub struct Disps(Rows<i8>);
+
+/// return the stable identifier for `disp`, its comma-separated steps with an
+/// explicit sign on each nonzero step, like `+1,0,-2`. unlike the position of
+/// `disp` within a [Disps], this does not change when other displacements are
+/// added or removed
+pub fn disp_id(disp: &[i8]) -> String {
+    disp.iter()
+        .map(|&d| {
+            if d == 0 {
+                "0".to_owned()
+            } else {
+                format!("{d:+}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// parse an identifier generated by [disp_id] back into a displacement
+pub fn parse_disp_id(id: &str) -> Option<Vec<i8>> {
+    id.split(',').map(|s| s.trim().parse().ok()).collect()
+}
+
+/// load a file of displacement identifiers and their energies, one pair per
+/// line separated by whitespace
+pub fn load_id_energies(
+    filename: impl AsRef<Path>,
+) -> std::io::Result<HashMap<String, f64>> {
+    let contents = std::fs::read_to_string(filename)?;
+    let mut ret = HashMap::new();
+    for line in contents.lines() {
+        let fields: Vec<_> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [] => continue,
+            [id, energy] => {
+                let energy = energy.parse().map_err(|e| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("invalid energy {energy} for {id}: {e}"),
+                    )
+                })?;
+                ret.insert(id.to_string(), energy);
+            }
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("expected an id and an energy, got `{line}`"),
+                ))
+            }
+        }
+    }
+    Ok(ret)
+}
 
 impl Disps {
     pub fn to_intder(&self, step_size: f64) -> Vec<Vec<f64>> {
         let mut ret = Vec::new();
-        for disp in &self.0 {
+        for disp in self.0.iter() {
             let disp: Vec<_> =
                 disp.iter().map(|i| *i as f64 * step_size).collect();
             ret.push(disp);
@@ -39,6 +189,72 @@ impl Disps {
         self.len() == 0
     }
 
+    /// iterate over views of the displacements in `self`
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &[i8]> + '_ {
+        self.0.iter()
+    }
+
+    /// return the index of `disp` in `self`, if present
+    pub fn position(&self, disp: &[i8]) -> Option<usize> {
+        self.0.binary_search(disp).ok()
+    }
+
+    /// return the stable identifiers of the displacements in `self`, in order.
+    /// see [disp_id]
+    pub fn ids(&self) -> Vec<String> {
+        self.0.iter().map(disp_id).collect()
+    }
+
+    /// return the energies for `self` from `energies`, a map of displacement
+    /// identifiers to energies. returns the identifier of the first
+    /// displacement without an energy as an error
+    pub fn energies_by_id(
+        &self,
+        energies: &HashMap<String, f64>,
+    ) -> Result<Vec<f64>, String> {
+        self.ids()
+            .into_iter()
+            .map(|id| energies.get(&id).copied().ok_or(id))
+            .collect()
+    }
+
+    /// return the displacements in `self` that are not in `other`
+    pub fn without(&self, other: &Disps) -> Disps {
+        Disps(self.0.filter(|d| other.position(d).is_none()))
+    }
+
+    /// assemble the energies for `self` from `parts`, pairs of displacements
+    /// and their corresponding energies, such as those from the two stages of
+    /// [Taylor::split_disps]. if a displacement occurs in more than one part,
+    /// the energy from the first is used. returns an error if a part has a
+    /// different number of displacements and energies, or for the first
+    /// displacement without an energy
+    pub fn merge_energies(
+        &self,
+        parts: &[(&Disps, &[f64])],
+    ) -> Result<Vec<f64>, MergeError> {
+        for (part, (disps, energies)) in parts.iter().enumerate() {
+            if disps.len() != energies.len() {
+                return Err(MergeError::LengthMismatch {
+                    part,
+                    disps: disps.len(),
+                    energies: energies.len(),
+                });
+            }
+        }
+        let mut ret = Vec::with_capacity(self.len());
+        'outer: for disp in self.0.iter() {
+            for (disps, energies) in parts {
+                if let Some(i) = disps.position(disp) {
+                    ret.push(energies[i]);
+                    continue 'outer;
+                }
+            }
+            return Err(MergeError::Missing(disp.to_vec()));
+        }
+        Ok(ret)
+    }
+
     #[cfg(test)]
     pub(crate) fn sort(&mut self) {
         self.0.sort()
@@ -51,7 +267,11 @@ impl IntoIterator for Disps {
     type IntoIter = std::vec::IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+        self.0
+            .iter()
+            .map(<[i8]>::to_vec)
+            .collect::<Vec<_>>()
+            .into_iter()
     }
 }
 
@@ -90,6 +310,14 @@ impl Taylor {
                 }
                 (Some(checks.clone()), Some(checks))
             }
+            // each of these checks requires an even number of coordinates that
+            // are odd under one of the generators of the group. no equivalence
+            // checks are needed since these cover every totally-symmetric
+            // product
+            C2h { .. } | D2h { .. } => parity_checks(
+                &irreps,
+                &generators(pg).expect("C2h and D2h have generators"),
+            ),
             // if we don't know how to handle the symmetry, just print a warning
             // and don't
             _ => {
@@ -99,137 +327,236 @@ impl Taylor {
         }
     }
 
-    /// returns the directly-derived Cartesian product row, where index is the
-    /// desired row index, n is the truncation order and m is the number of
-    /// variables in the Taylor series expansion. This corresponds to Algorithm
-    /// 3 from Thackston18 with the meanings of n and m reversed to actually
-    /// work
-    fn row(mut index: usize, n: usize, m: usize) -> Vec<usize> {
-        let mut ret = Vec::new();
-        for i in (0..n).rev() {
-            let ni = m.pow(i as u32);
-            let di = index / ni;
-            ret.push(di);
-            index -= di * ni;
+    /// advance `row` to the next exponent vector in lexicographic order whose
+    /// digits sum to less than the truncation order `m`, returning false once
+    /// the rows are exhausted. `sum` is the current sum of `row` and is
+    /// updated in place. this is the odometer-style equivalent of Algorithms 3
+    /// and 4 from Thackston18, so it never needs the flat index of a row and
+    /// cannot overflow for large numbers of variables
+    fn next_row(row: &mut [usize], sum: &mut usize, m: usize) -> bool {
+        let Some(last) = row.len().checked_sub(1) else {
+            return false;
+        };
+        if *sum + 1 < m {
+            row[last] += 1;
+            *sum += 1;
+            return true;
         }
-        ret
+        // the rightmost nonzero digit rolls over and carries into the digit to
+        // its left
+        let Some(i) = row.iter().rposition(|&d| d > 0) else {
+            return false;
+        };
+        if i == 0 {
+            return false;
+        }
+        *sum = *sum - row[i] + 1;
+        row[i] = 0;
+        row[i - 1] += 1;
+        true
     }
 
-    /// takes an invalid row of the Cartesian product, the number of variables
-    /// n, and the truncation order m and returns the index of the next valid
-    /// row. This corresponds to Algorithm 4 in Thackston18
-    fn next_row(mut row: Vec<usize>, n: usize, m: usize) -> usize {
-        for i in (0..n).rev() {
-            if row[i] > 0 {
-                row[i] = 0;
-                if i > 0 {
-                    row[i - 1] += 1;
+    /// return the number of rows in the unpruned expansion of order `m` in `n`
+    /// variables, the binomial coefficient C(n + m - 1, m - 1), or None if it
+    /// overflows a usize
+    pub fn num_rows(m: usize, n: usize) -> Option<usize> {
+        if m == 0 {
+            return Some(0);
+        }
+        let k = (m - 1).min(n);
+        let mut ret: usize = 1;
+        for i in 1..=k {
+            // exact at every step since ret is C(n + m - 1 - k + i, i)
+            ret = ret.checked_mul(n + m - 1 - k + i)? / i;
+        }
+        Some(ret)
+    }
+
+    /// check that an expansion of order `m` in `n` variables with `modchecks`
+    /// and `eqchecks` can be generated
+    fn validate(
+        m: usize,
+        n: usize,
+        modchecks: &Option<Checks>,
+        eqchecks: &Option<Checks>,
+    ) -> Result<(), Error> {
+        if m > MAX_ORDER {
+            return Err(Error::OrderTooLarge(m));
+        }
+        if Self::num_rows(m, n).is_none() {
+            return Err(Error::TooManyRows { m, n });
+        }
+        for checks in [modchecks, eqchecks].into_iter().flatten() {
+            for &c in checks.0.iter().flatten() {
+                if c == 0 || c > n {
+                    return Err(Error::InvalidCheck { check: c, n });
                 }
-                break;
             }
         }
-        let mut index = 0;
-        let lr = row.len();
-        for i in (0..n).rev() {
-            index += row[lr - i - 1] * m.pow(i as u32);
-        }
-        index
+        Ok(())
     }
 
+    /// like [Taylor::try_new] but panicking on invalid input
     pub fn new(
         m: usize,
         n: usize,
         modchecks: Option<Checks>,
         eqchecks: Option<Checks>,
     ) -> Self {
-        let last_index = m.pow(n as u32);
-        let mut forces = Vec::new();
-        let mut i = 0;
-        while i < last_index {
-            let row = Self::row(i, n, m);
-            let s: usize = row.iter().sum();
-            if s < m {
-                let mc = if let Some(checks) = &modchecks {
-                    checks.mod_check(&row)
-                } else {
-                    true
-                };
-                let ec = if let Some(checks) = &eqchecks {
-                    checks.eq_check(&row)
-                } else {
-                    true
-                };
-                if (modchecks.is_none() && !ec)
-                    || (eqchecks.is_none() && !mc)
-                    || (!ec && !mc)
-                {
-                    i += 1;
-                    continue;
-                }
-                forces.push(row.iter().map(|&r| r as u8).collect());
-                i += 1;
-            } else {
-                i = Self::next_row(row, n, m);
+        Self::try_new(m, n, modchecks, eqchecks)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// generate the rows of force constants for a Taylor series expansion of
+    /// order `m` - 1 in `n` variables, pruned by `modchecks` and `eqchecks`
+    pub fn try_new(
+        m: usize,
+        n: usize,
+        modchecks: Option<Checks>,
+        eqchecks: Option<Checks>,
+    ) -> Result<Self, Error> {
+        Self::validate(m, n, &modchecks, &eqchecks)?;
+        let forces = if m == 0 {
+            ForceMatrix::new(n)
+        } else {
+            Self::rows(n, None, m, &modchecks, &eqchecks)
+        };
+        Ok(Self { forces })
+    }
+
+    /// like [Taylor::new] but generating the rows in parallel. the rows are
+    /// partitioned by their leading digit, and the partitions are concatenated
+    /// in order, so the result is identical to [Taylor::new]
+    pub fn new_par(
+        m: usize,
+        n: usize,
+        modchecks: Option<Checks>,
+        eqchecks: Option<Checks>,
+    ) -> Self {
+        Self::try_new_par(m, n, modchecks, eqchecks)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// like [Taylor::try_new] but generating the rows in parallel, as in
+    /// [Taylor::new_par]
+    pub fn try_new_par(
+        m: usize,
+        n: usize,
+        modchecks: Option<Checks>,
+        eqchecks: Option<Checks>,
+    ) -> Result<Self, Error> {
+        if n == 0 || m == 0 {
+            return Self::try_new(m, n, modchecks, eqchecks);
+        }
+        Self::validate(m, n, &modchecks, &eqchecks)?;
+        let parts: Vec<_> = (0..m)
+            .into_par_iter()
+            .map(|lead| Self::rows(n, Some(lead), m, &modchecks, &eqchecks))
+            .collect();
+        let mut forces = ForceMatrix::new(n);
+        for part in &parts {
+            forces.append(part);
+        }
+        Ok(Self { forces })
+    }
+
+    /// return the rows of the expansion in `n` variables, applying `modchecks`
+    /// and `eqchecks`. if `lead` is provided, only the rows with that leading
+    /// digit are returned
+    fn rows(
+        n: usize,
+        lead: Option<usize>,
+        m: usize,
+        modchecks: &Option<Checks>,
+        eqchecks: &Option<Checks>,
+    ) -> ForceMatrix {
+        let mut row = vec![0; n];
+        if let Some(lead) = lead {
+            row[0] = lead;
+        }
+        let mut sum: usize = row.iter().sum();
+        let mut forces = ForceMatrix::new(n);
+        loop {
+            if Self::keep(&row, modchecks, eqchecks) {
+                forces
+                    .try_push(&row)
+                    .expect("exponents are bounded by MAX_ORDER");
+            }
+            if !Self::next_row(&mut row, &mut sum, m)
+                || lead.is_some_and(|l| row[0] != l)
+            {
+                break;
             }
         }
-        Self { forces }
-    }
-
-    /// CartProd returns the Cartesian product of the elements in prods.
-    /// Implementation adapted from
-    /// https://docs.python.org/3/library/itertools.html#itertools.product
-    fn cart_prod(pools: Vec<Vec<i8>>) -> Vec<Vec<i8>> {
-        let mut result = vec![vec![]];
-        for pool in pools {
-            let mut tmp = Vec::new();
-            for x in result {
-                for y in &pool {
-                    let l = x.len() + 1;
-                    let mut a = x.clone();
-                    a.resize(l, 0);
-                    a[l - 1] = *y;
-                    tmp.push(a);
-                }
+        forces
+    }
+
+    /// report whether `row` survives the pruning by `modchecks` and
+    /// `eqchecks`
+    fn keep(
+        row: &[usize],
+        modchecks: &Option<Checks>,
+        eqchecks: &Option<Checks>,
+    ) -> bool {
+        let mc = if let Some(checks) = modchecks {
+            checks.mod_check(row)
+        } else {
+            true
+        };
+        let ec = if let Some(checks) = eqchecks {
+            checks.eq_check(row)
+        } else {
+            true
+        };
+        !((modchecks.is_none() && !ec)
+            || (eqchecks.is_none() && !mc)
+            || (!ec && !mc))
+    }
+
+    /// count the force constants of each order that survive and are
+    /// eliminated by `modchecks` and `eqchecks` in the expansion of order
+    /// `m` - 1 in the coordinates with `irreps`, broken down by the irreps of
+    /// the coordinates each one involves
+    pub fn census(
+        m: usize,
+        irreps: &[Irrep],
+        modchecks: &Option<Checks>,
+        eqchecks: &Option<Checks>,
+    ) -> Result<Census, Error> {
+        let n = irreps.len();
+        Self::validate(m, n, modchecks, eqchecks)?;
+        let mut ret = Census::default();
+        if m == 0 {
+            return Ok(ret);
+        }
+        let mut row = vec![0; n];
+        let mut sum = 0;
+        while Self::next_row(&mut row, &mut sum, m) {
+            let mut involved: Vec<_> = row
+                .iter()
+                .zip(irreps)
+                .filter(|(&e, _)| e > 0)
+                .map(|(_, &irrep)| irrep)
+                .collect();
+            involved.sort();
+            involved.dedup();
+            let count =
+                ret.0.entry(sum).or_default().entry(involved).or_default();
+            if Self::keep(&row, modchecks, eqchecks) {
+                count.kept += 1;
+            } else {
+                count.pruned += 1;
             }
-            result = tmp;
         }
-        result
+        Ok(ret)
     }
 
     /// return the displacements associated with the expansion described by
     /// `self`
     pub fn disps(&self) -> Disps {
-        let mut disps = Vec::new();
-        for row in &self.forces {
-            let mut indices = Vec::new();
-            let mut values = Vec::new();
-            for (i, digit) in row.iter().enumerate() {
-                if *digit != 0 {
-                    indices.push(i);
-                    values.push(digit);
-                }
-            }
-            if values.is_empty() {
-                disps.push(row.iter().map(|u| *u as i8).collect());
-                continue;
-            }
-            let mut prods = Vec::new();
-            for digit in values {
-                let digit = *digit as i8;
-                let mut tmp = Vec::new();
-                for j in (-digit..=digit).step_by(2) {
-                    tmp.push(j);
-                }
-                prods.push(tmp);
-            }
-            let new_rows = Self::cart_prod(prods);
-            for nrow in new_rows {
-                let mut r: Vec<_> = row.iter().map(|u| *u as i8).collect();
-                for (i, index) in indices.iter().enumerate() {
-                    r[*index] = nrow[i];
-                }
-                disps.push(r);
-            }
+        let mut disps = Rows::new(self.forces.ncols());
+        for row in self.forces.iter() {
+            Self::row_disps(row, &mut disps);
         }
         // sort -u on disps
         disps.sort();
@@ -237,6 +564,111 @@ impl Taylor {
         Disps(disps)
     }
 
+    /// like [Taylor::disps] but expanding the rows of `self` in parallel. the
+    /// displacements are sorted and deduplicated afterward, so the result is
+    /// identical to [Taylor::disps]
+    pub fn disps_par(&self) -> Disps {
+        let ncols = self.forces.ncols();
+        let mut disps = (0..self.forces.len())
+            .into_par_iter()
+            .fold(
+                || Rows::new(ncols),
+                |mut acc, i| {
+                    Self::row_disps(&self.forces[i], &mut acc);
+                    acc
+                },
+            )
+            .reduce(
+                || Rows::new(ncols),
+                |mut a, b| {
+                    a.append(&b);
+                    a
+                },
+            );
+        disps.par_sort();
+        disps.dedup();
+        Disps(disps)
+    }
+
+    /// push the displacements needed for the single row of force constants
+    /// `row` onto `disps`. each nonzero exponent d is displaced by -d, -d + 2,
+    /// ..., d steps, in every combination with the other nonzero exponents
+    fn row_disps(row: &[u8], disps: &mut Rows<i8>) {
+        let mut disp: Vec<i8> = row
+            .iter()
+            .map(|&d| {
+                -i8::try_from(d).expect("exponents are bounded by MAX_ORDER")
+            })
+            .collect();
+        loop {
+            disps.push(&disp);
+            // odometer over the nonzero exponents, rightmost fastest
+            let mut i = disp.len();
+            loop {
+                let Some(j) = i.checked_sub(1) else {
+                    return;
+                };
+                i = j;
+                let d = row[i] as i8;
+                if d == 0 {
+                    continue;
+                }
+                if disp[i] < d {
+                    disp[i] += 2;
+                    break;
+                }
+                disp[i] = -d;
+            }
+        }
+    }
+
+    /// return the subset of `self` needed to determine the quadratic force
+    /// constants
+    pub fn harmonic(&self) -> Self {
+        Self {
+            forces: self.forces.filter(|row| {
+                row.iter().map(|&r| r as usize).sum::<usize>() <= 2
+            }),
+        }
+    }
+
+    /// return a copy of `self` with the polynomial terms whose exponents are
+    /// in `terms` removed, such as for testing whether a fit really needs them
+    pub fn without_terms(&self, terms: &[Vec<u8>]) -> Self {
+        Self {
+            forces: self.forces.filter(|row| !terms.iter().any(|t| t == row)),
+        }
+    }
+
+    /// split the displacements associated with `self` into those needed for
+    /// the quadratic force constants and the remainder, needed only for the
+    /// cubic and higher force constants. this allows the cheaper harmonic
+    /// displacements to be run and checked first
+    pub fn split_disps(&self) -> (Disps, Disps) {
+        let harm = self.harmonic().disps();
+        let rest = self.disps().without(&harm);
+        (harm, rest)
+    }
+
+    /// return the displacements required by `self` that are not already in
+    /// `existing`, such as the displacements of a lower-order expansion with
+    /// the same checks whose energies have already been computed
+    pub fn new_disps(&self, existing: &Disps) -> Disps {
+        self.disps().without(existing)
+    }
+
+    /// assemble the energies for all of the displacements of `self`, in the
+    /// order of [Taylor::disps], from the `existing` displacements and
+    /// energies and those for the displacements returned by
+    /// [Taylor::new_disps]. returns an error as for [Disps::merge_energies]
+    pub fn merge_energies(
+        &self,
+        existing: (&Disps, &[f64]),
+        new: (&Disps, &[f64]),
+    ) -> Result<Vec<f64>, MergeError> {
+        self.disps().merge_energies(&[existing, new])
+    }
+
     pub fn to_anpass(
         &self,
         taylor_disps: &Disps,
@@ -244,24 +676,20 @@ impl Taylor {
         step_size: f64,
     ) -> Anpass {
         let mut disps = Vec::new();
-        for disp in &taylor_disps.0 {
+        for disp in taylor_disps.iter() {
             for coord in disp {
                 disps.push(*coord as f64 * step_size);
             }
         }
         let tdl = taylor_disps.len();
         let fl = self.forces.len();
-        let mut fs = Vec::new();
-        for row in &self.forces {
-            for c in row {
-                fs.push(*c as i32);
-            }
-        }
+        let fs: Vec<_> =
+            self.forces.as_slice().iter().map(|&c| c as i32).collect();
         Anpass {
             disps: na::DMatrix::from_row_slice(tdl, disps.len() / tdl, &disps),
             energies: na::DVector::from_row_slice(energies),
             exponents: na::DMatrix::from_column_slice(
-                self.forces[0].len(),
+                self.forces.ncols(),
                 fl,
                 &fs,
             ),
@@ -270,6 +698,48 @@ impl Taylor {
     }
 }
 
+/// helper function for generating the mod checks for point groups described
+/// entirely by the parity of the coordinates under each of their generators.
+/// `odd` lists the irreps odd under each generator, as returned by
+/// [generators]
+fn parity_checks(
+    irreps: &[(usize, Irrep)],
+    odd: &[Vec<Irrep>],
+) -> (Option<Checks>, Option<Checks>) {
+    let mut checks = Checks::default();
+    for (i, irrep) in irreps {
+        for (check, odd) in checks.0.iter_mut().zip(odd) {
+            if odd.contains(irrep) {
+                check.push(i + 1);
+            }
+        }
+    }
+    (Some(checks), None)
+}
+
+/// return the irreps that change sign under each generator of `pg`, or None
+/// if `pg` is not one of the abelian groups supported by [Taylor::make_checks]
+pub(crate) fn generators(pg: &PointGroup) -> Option<Vec<Vec<Irrep>>> {
+    use symm::Irrep::*;
+    use symm::PointGroup::*;
+    Some(match pg {
+        C1 => vec![],
+        Cs { .. } => vec![vec![App]],
+        C2 { .. } => vec![vec![B]],
+        // the C2 rotation and one of the mirror planes
+        C2v { .. } => vec![vec![B1, B2], vec![A2, B2]],
+        // inversion and the C2 rotation
+        C2h { .. } => vec![vec![Au, Bu], vec![Bg, Bu]],
+        // inversion and the C2 rotations associated with B1 and B2
+        D2h { .. } => vec![
+            vec![Au, B1u, B2u, B3u],
+            vec![B2g, B3g, B2u, B3u],
+            vec![B1g, B3g, B1u, B3u],
+        ],
+        _ => return None,
+    })
+}
+
 /// helper function for generating the checks for C2 and Cs point groups.
 /// `target` is the irrep to treat specially. it should be `B` for C2 and `App`
 /// for Cs
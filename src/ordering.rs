@@ -0,0 +1,72 @@
+//! reordering and negating the SICs of an input file before the expansion is
+//! generated from them
+
+use symm::Irrep;
+
+/// an invalid SIC index or ordering
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// a 1-based SIC index outside of 1..=n
+    OutOfRange { sic: usize, n: usize },
+    /// a custom order that doesn't contain each of the n SICs once
+    NotPermutation(usize),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::OutOfRange { sic, n } => {
+                write!(f, "SIC {sic} is out of range for {n} SICs")
+            }
+            Error::NotPermutation(n) => {
+                write!(f, "the order must contain each of the {n} SICs once")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// reverse the signs of the SICs in `sics` at the 1-based indices in `negate`
+pub fn negate(sics: &mut [Vec<f64>], negate: &[usize]) -> Result<(), Error> {
+    let n = sics.len();
+    if let Some(&sic) = negate.iter().find(|&&i| i < 1 || i > n) {
+        return Err(Error::OutOfRange { sic, n });
+    }
+    for &i in negate {
+        for c in &mut sics[i - 1] {
+            *c = -*c;
+        }
+    }
+    Ok(())
+}
+
+/// return the permutation putting SICs with `irreps` in their output order,
+/// where element i is the 0-based input index of output SIC i. `custom` gives
+/// the 1-based input indices in output order. without it, the SICs are
+/// grouped by irrep if `by_irrep` is true, keeping the input order within
+/// each irrep, or left in the input order
+pub fn permutation(
+    irreps: &[Irrep],
+    custom: Option<&[usize]>,
+    by_irrep: bool,
+) -> Result<Vec<usize>, Error> {
+    let n = irreps.len();
+    match custom {
+        Some(order) => {
+            let mut sorted = order.to_vec();
+            sorted.sort();
+            if !sorted.iter().copied().eq(1..=n) {
+                return Err(Error::NotPermutation(n));
+            }
+            Ok(order.iter().map(|i| i - 1).collect())
+        }
+        None => {
+            let mut perm: Vec<_> = (0..n).collect();
+            if by_irrep {
+                perm.sort_by_key(|&i| irreps[i]);
+            }
+            Ok(perm)
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! a serializable record of a whole expansion project, allowing each stage of
+//! a calculation to resume without re-deriving the symmetry or regenerating the
+//! expansion
+
+use std::path::Path;
+
+use intder::{Intder, Siic};
+use serde::{Deserialize, Serialize};
+use symm::{Atom, Irrep, Molecule, PointGroup};
+
+use crate::{
+    fcs::{self, Fc},
+    Checks, Disps, Taylor,
+};
+
+/// a serializable mirror of the simple internal coordinates in [Siic]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Coord {
+    Stretch(usize, usize),
+    Bend(usize, usize, usize),
+    Torsion(usize, usize, usize, usize),
+    Lin1(usize, usize, usize, usize),
+    Out(usize, usize, usize, usize),
+}
+
+impl TryFrom<&Siic> for Coord {
+    type Error = String;
+
+    fn try_from(value: &Siic) -> Result<Self, Self::Error> {
+        Ok(match *value {
+            Siic::Stretch(a, b) => Coord::Stretch(a, b),
+            Siic::Bend(a, b, c) => Coord::Bend(a, b, c),
+            Siic::Torsion(a, b, c, d) => Coord::Torsion(a, b, c, d),
+            Siic::Lin1(a, b, c, d) => Coord::Lin1(a, b, c, d),
+            Siic::Out(a, b, c, d) => Coord::Out(a, b, c, d),
+            _ => return Err(format!("{value} cannot be saved in a project")),
+        })
+    }
+}
+
+impl From<&Coord> for Siic {
+    fn from(value: &Coord) -> Self {
+        match *value {
+            Coord::Stretch(a, b) => Siic::Stretch(a, b),
+            Coord::Bend(a, b, c) => Siic::Bend(a, b, c),
+            Coord::Torsion(a, b, c, d) => Siic::Torsion(a, b, c, d),
+            Coord::Lin1(a, b, c, d) => Siic::Lin1(a, b, c, d),
+            Coord::Out(a, b, c, d) => Siic::Out(a, b, c, d),
+        }
+    }
+}
+
+/// everything needed to pick up an expansion where a previous run left off.
+/// the fields after `step_size` are filled in as the corresponding stages are
+/// reached
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    /// the atomic symbols of the atoms
+    pub atoms: Vec<String>,
+
+    /// the normalized reference geometry in bohr, without dummy atoms
+    pub geom: Vec<[f64; 3]>,
+
+    pub simple_internals: Vec<Coord>,
+
+    /// the symmetry internal coordinates, already sorted into the order used
+    /// for the expansion
+    pub symmetry_internals: Vec<Vec<f64>>,
+
+    pub point_group: PointGroup,
+
+    /// the irrep of each entry in `symmetry_internals`
+    pub irreps: Vec<Irrep>,
+
+    /// the mod and equivalence checks used for the expansion
+    pub checks: (Option<Checks>, Option<Checks>),
+
+    /// the truncation order of the expansion
+    pub order: usize,
+
+    pub step_size: f64,
+
+    pub taylor: Option<Taylor>,
+
+    pub disps: Option<Disps>,
+
+    /// the energies for each of `disps`, in the same order
+    pub energies: Option<Vec<f64>>,
+
+    /// the fitted SIC force constants
+    pub fcs: Option<Vec<Fc>>,
+}
+
+impl Project {
+    /// load a project from the JSON file at `path`. returns an error if its
+    /// force constants refer to SICs it doesn't have
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let f = std::fs::File::open(path)?;
+        let ret: Self = serde_json::from_reader(std::io::BufReader::new(f))?;
+        if let Some(fcs) = &ret.fcs {
+            fcs::check_indices(fcs, ret.symmetry_internals.len())?;
+        }
+        Ok(ret)
+    }
+
+    /// write `self` to `path` as JSON
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let f = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(std::io::BufWriter::new(f), self)?;
+        Ok(())
+    }
+
+    /// return the reference geometry as a [Molecule]
+    pub fn molecule(&self) -> Molecule {
+        Molecule::new(
+            self.atoms
+                .iter()
+                .zip(&self.geom)
+                .map(|(l, g)| Atom::new_from_label(l, g[0], g[1], g[2]))
+                .collect(),
+        )
+    }
+
+    /// replace the geometry and internal coordinates in `intder` with those
+    /// from `self`, along with the counts of them in its input options, adding
+    /// dummy atoms along the principal axis of the point group if it has one.
+    /// returns the number of dummy atoms added
+    pub fn apply(&self, intder: &mut Intder) -> usize {
+        intder.geom = self.molecule().into();
+        intder.simple_internals =
+            self.simple_internals.iter().map(Siic::from).collect();
+        intder.symmetry_internals = self.symmetry_internals.clone();
+        intder.input_options[1] = intder.simple_internals.len();
+        intder.input_options[2] = intder.symmetry_internals.len();
+        if let Some(axis) = self.point_group.axis() {
+            intder.add_dummies(axis)
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::ops::Index;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// a row-major matrix stored in a single contiguous buffer, used for the rows
+/// of force constant exponents and displacements to avoid one heap allocation
+/// per row in large expansions. it is serialized as a list of rows, the same
+/// as the `Vec<Vec<T>>` it replaced
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    try_from = "Nested<T>",
+    into = "Nested<T>",
+    bound(
+        serialize = "T: Copy + Serialize",
+        deserialize = "T: Copy + Deserialize<'de>"
+    )
+)]
+pub struct Rows<T> {
+    data: Vec<T>,
+    nrows: usize,
+    ncols: usize,
+}
+
+/// the exponents of each force constant in a [crate::Taylor] expansion
+pub type ForceMatrix = Rows<u8>;
+
+impl<T: Copy> Rows<T> {
+    /// construct an empty matrix whose rows will have `ncols` columns
+    pub fn new(ncols: usize) -> Self {
+        Self {
+            data: Vec::new(),
+            nrows: 0,
+            ncols,
+        }
+    }
+
+    /// the number of rows in `self`
+    pub fn len(&self) -> usize {
+        self.nrows
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// the number of columns in each row of `self`
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// return a view of row `i`
+    pub fn row(&self, i: usize) -> &[T] {
+        &self.data[i * self.ncols..(i + 1) * self.ncols]
+    }
+
+    /// iterate over views of the rows of `self`
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &[T]> + '_ {
+        (0..self.nrows).map(|i| self.row(i))
+    }
+
+    /// the underlying row-major buffer
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// append `row` to `self`
+    pub fn push(&mut self, row: &[T]) {
+        assert_eq!(
+            row.len(),
+            self.ncols,
+            "row has the wrong number of columns"
+        );
+        self.data.extend_from_slice(row);
+        self.nrows += 1;
+    }
+
+    /// convert each element of `row` to `T` and append it to `self`, leaving
+    /// `self` unchanged and returning the error if any element doesn't fit
+    pub fn try_push<U>(&mut self, row: &[U]) -> Result<(), T::Error>
+    where
+        U: Copy,
+        T: TryFrom<U>,
+    {
+        assert_eq!(
+            row.len(),
+            self.ncols,
+            "row has the wrong number of columns"
+        );
+        let start = self.data.len();
+        for &u in row {
+            match T::try_from(u) {
+                Ok(t) => self.data.push(t),
+                Err(e) => {
+                    self.data.truncate(start);
+                    return Err(e);
+                }
+            }
+        }
+        self.nrows += 1;
+        Ok(())
+    }
+
+    /// append all of the rows of `other` to `self`
+    pub fn append(&mut self, other: &Self) {
+        assert_eq!(other.ncols, self.ncols);
+        self.data.extend_from_slice(&other.data);
+        self.nrows += other.nrows;
+    }
+
+    /// return a new matrix containing the rows of `self` for which `f` returns
+    /// true
+    pub fn filter(&self, mut f: impl FnMut(&[T]) -> bool) -> Self {
+        let mut ret = Self::new(self.ncols);
+        for row in self.iter().filter(|r| f(r)) {
+            ret.push(row);
+        }
+        ret
+    }
+}
+
+impl<T: Copy + Ord> Rows<T> {
+    /// sort the rows of `self` lexicographically
+    pub fn sort(&mut self) {
+        let mut order: Vec<_> = (0..self.nrows).collect();
+        order.sort_by(|&a, &b| self.row(a).cmp(self.row(b)));
+        self.permute(&order);
+    }
+
+    /// remove consecutive repeated rows from `self`, like [Vec::dedup]
+    pub fn dedup(&mut self) {
+        if self.nrows < 2 {
+            return;
+        }
+        let n = self.ncols;
+        let mut kept = 1;
+        for i in 1..self.nrows {
+            if self.data[i * n..(i + 1) * n]
+                != self.data[(kept - 1) * n..kept * n]
+            {
+                self.data.copy_within(i * n..(i + 1) * n, kept * n);
+                kept += 1;
+            }
+        }
+        self.data.truncate(kept * n);
+        self.nrows = kept;
+    }
+
+    /// rearrange the rows of `self` so that row `i` is the old row `order[i]`
+    fn permute(&mut self, order: &[usize]) {
+        let mut data = Vec::with_capacity(self.data.len());
+        for &i in order {
+            data.extend_from_slice(self.row(i));
+        }
+        self.data = data;
+    }
+
+    /// return the index of `row` in `self`, which must already be sorted, or
+    /// the index where it could be inserted as an error
+    pub fn binary_search(&self, row: &[T]) -> Result<usize, usize> {
+        let (mut lo, mut hi) = (0, self.nrows);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.row(mid).cmp(row) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Ok(mid),
+            }
+        }
+        Err(lo)
+    }
+}
+
+impl<T: Copy + Ord + Send + Sync> Rows<T> {
+    /// like [Rows::sort] but sorting in parallel
+    pub fn par_sort(&mut self) {
+        let mut order: Vec<_> = (0..self.nrows).collect();
+        order.par_sort_unstable_by(|&a, &b| self.row(a).cmp(self.row(b)));
+        self.permute(&order);
+    }
+}
+
+impl<T: Copy> Index<usize> for Rows<T> {
+    type Output = [T];
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.row(index)
+    }
+}
+
+impl<T: Copy> From<Vec<Vec<T>>> for Rows<T> {
+    /// panics if the rows of `value` have different lengths
+    fn from(value: Vec<Vec<T>>) -> Self {
+        let mut ret = Self::new(value.first().map_or(0, Vec::len));
+        for row in &value {
+            ret.push(row);
+        }
+        ret
+    }
+}
+
+/// the serialized form of [Rows]. this wraps the nested rows because the
+/// infallible [From] conversion from `Vec<Vec<T>>` rules out implementing
+/// [TryFrom] on it directly
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct Nested<T>(Vec<Vec<T>>);
+
+impl<T: Copy> TryFrom<Nested<T>> for Rows<T> {
+    type Error = String;
+
+    /// returns an error if the rows have different lengths
+    fn try_from(value: Nested<T>) -> Result<Self, Self::Error> {
+        let ncols = value.0.first().map_or(0, Vec::len);
+        let mut ret = Self::new(ncols);
+        for (i, row) in value.0.iter().enumerate() {
+            if row.len() != ncols {
+                return Err(format!(
+                    "row {i} has {} columns instead of {ncols}",
+                    row.len()
+                ));
+            }
+            ret.push(row);
+        }
+        Ok(ret)
+    }
+}
+
+impl<T: Copy> From<Rows<T>> for Nested<T> {
+    fn from(value: Rows<T>) -> Self {
+        Self(value.iter().map(<[T]>::to_vec).collect())
+    }
+}
+
+impl<T: Copy + PartialEq> PartialEq<Vec<Vec<T>>> for Rows<T> {
+    fn eq(&self, other: &Vec<Vec<T>>) -> bool {
+        self.nrows == other.len()
+            && self.iter().zip(other).all(|(a, b)| a == b.as_slice())
+    }
+}
@@ -0,0 +1,134 @@
+//! writing Cartesian force constants in the fort.15, fort.30, and fort.40
+//! layouts read by spectro
+
+use std::{
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use intder::{Intder, Siic};
+use nalgebra as na;
+
+use crate::{
+    fcs::Fc,
+    internals::{geom_ang, Unsupported, BOHR_TO_ANG},
+    transform::{flat_index, to_cartesian, SicDerivs},
+};
+
+/// Cartesian force constants in hartree/bohrⁿ. the cubic and quartic force
+/// constants are stored as full, symmetric tensors in row-major order
+#[derive(Clone, Debug, PartialEq)]
+pub struct CartFcs {
+    /// the number of real atoms
+    pub natoms: usize,
+    pub fc2: na::DMatrix<f64>,
+    pub fc3: Vec<f64>,
+    pub fc4: Vec<f64>,
+}
+
+impl CartFcs {
+    /// transform the SIC force constants `fcs`, in hartree/Åⁿ and radians,
+    /// for the coordinates in `intder` to Cartesian coordinates, including the
+    /// curvature of the internal coordinates in the cubic and quartic terms.
+    /// returns an error if any of the simple internals are not supported
+    pub fn new(intder: &Intder, fcs: &[Fc]) -> Result<Self, Unsupported> {
+        Self::from_parts(
+            &intder.simple_internals,
+            &intder.symmetry_internals,
+            &geom_ang(intder),
+            intder.atoms.len(),
+            fcs,
+        )
+    }
+
+    /// like [CartFcs::new] but taking the individual pieces of an intder
+    /// input. `geom` is in Ångstrom and may contain dummy atoms after the first
+    /// `natoms` real atoms
+    pub fn from_parts(
+        siics: &[Siic],
+        sics: &[Vec<f64>],
+        geom: &[na::Vector3<f64>],
+        natoms: usize,
+        fcs: &[Fc],
+    ) -> Result<Self, Unsupported> {
+        let ncart = 3 * natoms;
+        let derivs = SicDerivs::new(siics, sics, geom, natoms)?;
+        let cart = to_cartesian(fcs, &derivs);
+        let to_bohr = |v: Vec<f64>, n: i32| -> Vec<f64> {
+            let f = BOHR_TO_ANG.powi(n);
+            v.into_iter().map(|x| x * f).collect()
+        };
+        let fc2 = to_bohr(cart.fc2, 2);
+        let fc3 = to_bohr(cart.fc3, 3);
+        let fc4 = to_bohr(cart.fc4, 4);
+        Ok(Self {
+            natoms,
+            fc2: na::DMatrix::from_row_slice(ncart, ncart, &fc2),
+            fc3,
+            fc4,
+        })
+    }
+
+    /// return the unique cubic force constants in spectro's order, with
+    /// i >= j >= k
+    pub fn unique_fc3(&self) -> Vec<f64> {
+        let n = 3 * self.natoms;
+        let mut ret = Vec::new();
+        for i in 0..n {
+            for j in 0..=i {
+                for k in 0..=j {
+                    ret.push(self.fc3[flat_index(&[i, j, k], n)]);
+                }
+            }
+        }
+        ret
+    }
+
+    /// return the unique quartic force constants in spectro's order, with
+    /// i >= j >= k >= l
+    pub fn unique_fc4(&self) -> Vec<f64> {
+        let n = 3 * self.natoms;
+        let mut ret = Vec::new();
+        for i in 0..n {
+            for j in 0..=i {
+                for k in 0..=j {
+                    for l in 0..=k {
+                        ret.push(self.fc4[flat_index(&[i, j, k, l], n)]);
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    /// write fort.15, fort.30, and fort.40 to the directory `dir`
+    pub fn write_spectro(&self, dir: impl AsRef<Path>) -> std::io::Result<()> {
+        let dir = dir.as_ref();
+        let fc2: Vec<_> = self.fc2.transpose().iter().copied().collect();
+        write_fort(dir.join("fort.15"), self.natoms, 6 * self.natoms, &fc2)?;
+        let fc3 = self.unique_fc3();
+        write_fort(dir.join("fort.30"), self.natoms, fc3.len(), &fc3)?;
+        let fc4 = self.unique_fc4();
+        write_fort(dir.join("fort.40"), self.natoms, fc4.len(), &fc4)?;
+        Ok(())
+    }
+}
+
+/// write a spectro force constant file with a header of the number of atoms
+/// and `count`, followed by `vals` three to a line
+fn write_fort(
+    path: impl AsRef<Path>,
+    natoms: usize,
+    count: usize,
+    vals: &[f64],
+) -> std::io::Result<()> {
+    let mut f = BufWriter::new(std::fs::File::create(path)?);
+    writeln!(f, "{natoms:5}{count:5}")?;
+    for chunk in vals.chunks(3) {
+        for v in chunk {
+            write!(f, "{v:20.10}")?;
+        }
+        writeln!(f)?;
+    }
+    f.flush()
+}
@@ -75,7 +75,7 @@ fn test_forces_with_zero_checks() {
 #[test]
 fn test_disps() {
     let got = Taylor::new(5, 3, None, None).disps();
-    let mut want = Disps(load_vec::<i8>("testfiles/dispu.h2o.txt"));
+    let mut want = Disps(load_vec::<i8>("testfiles/dispu.h2o.txt").into());
     // the order doesn't matter, so let rust sort both
     want.sort();
     assert_eq!(got, want);
@@ -90,7 +90,7 @@ fn test_disps_with_checks() {
         Some(Checks([vec![5, 6, 7], vec![8], vec![9]])),
     )
     .disps();
-    let mut want = Disps(load_vec::<i8>("testfiles/dispu.c3h2.mod.txt"));
+    let mut want = Disps(load_vec::<i8>("testfiles/dispu.c3h2.mod.txt").into());
     want.sort();
     assert_eq!(got, want);
 }
@@ -104,7 +104,7 @@ fn test_disps_with_zero_checks() {
         Some(Checks([vec![3], vec![], vec![]])),
     )
     .disps();
-    let mut want = Disps(load_vec::<i8>("testfiles/dispu.h2o.mod.txt"));
+    let mut want = Disps(load_vec::<i8>("testfiles/dispu.h2o.mod.txt").into());
     want.sort();
     assert_eq!(got, want);
 }
@@ -155,3 +155,1018 @@ fn make_checks_cs() {
     let want = (Some(w.clone()), Some(w));
     assert_eq!(got, want);
 }
+
+#[test]
+fn make_checks_d2h() {
+    use Irrep::*;
+    // CO2 with the symmetric and antisymmetric stretches and the degenerate
+    // pair of linear bends
+    let irreps = vec![(0, Ag), (1, B1u), (2, B2u), (3, B3u)];
+    let pg = PointGroup::D2h {
+        axes: [Axis::Z, Axis::Y, Axis::X],
+        planes: [
+            Plane(Axis::X, Axis::Y),
+            Plane(Axis::X, Axis::Z),
+            Plane(Axis::Y, Axis::Z),
+        ],
+    };
+    let (modchecks, eqchecks) = Taylor::make_checks(irreps, &pg);
+    assert_eq!(
+        modchecks,
+        Some(Checks([vec![2, 3, 4], vec![3, 4], vec![2, 4]]))
+    );
+    assert_eq!(eqchecks, None);
+
+    let taylor = Taylor::new(5, 4, modchecks, eqchecks);
+    let has = |row: &[u8]| taylor.forces.iter().any(|r| r == row);
+    assert!(has(&[0, 2, 0, 0]));
+    assert!(has(&[0, 0, 2, 2]));
+    assert!(!has(&[0, 1, 0, 0]));
+    // B2u x B3u = B1g
+    assert!(!has(&[0, 0, 1, 1]));
+    // B1u x B2u x B3u = Au
+    assert!(!has(&[0, 1, 1, 1]));
+    assert!(!has(&[0, 2, 1, 1]));
+
+    let pg = PointGroup::C2h {
+        axis: Axis::Z,
+        plane: Plane(Axis::X, Axis::Y),
+    };
+    let irreps = vec![(0, Ag), (1, Bu), (2, Au), (3, Bg)];
+    let (modchecks, _) = Taylor::make_checks(irreps, &pg);
+    assert_eq!(modchecks, Some(Checks([vec![2, 3], vec![2, 4], vec![]])));
+}
+
+#[test]
+fn census() {
+    use Irrep::*;
+    let irreps = [A1, A1, B2];
+    let pg = PointGroup::C2v {
+        axis: Axis::Z,
+        planes: [Plane(Axis::X, Axis::Z), Plane(Axis::Y, Axis::Z)],
+    };
+    let (modchecks, eqchecks) =
+        Taylor::make_checks(irreps.iter().copied().enumerate().collect(), &pg);
+    let census = Taylor::census(5, &irreps, &modchecks, &eqchecks).unwrap();
+
+    // the kept force constants are exactly the rows of the expansion
+    let taylor = Taylor::new(5, 3, modchecks, eqchecks);
+    let totals = census.totals();
+    for (order, total) in &totals {
+        let want = taylor
+            .forces
+            .iter()
+            .filter(|r| r.iter().map(|&e| e as usize).sum::<usize>() == *order)
+            .count();
+        assert_eq!(total.kept, want);
+        assert_eq!(
+            total.kept + total.pruned,
+            Taylor::num_rows(order + 1, 3).unwrap()
+                - Taylor::num_rows(*order, 3).unwrap()
+        );
+    }
+
+    // the lone B2 coordinate survives only at even orders
+    let b2 = |order| census.0[&order][&vec![B2]];
+    assert_eq!(b2(1), census::Count { kept: 0, pruned: 1 });
+    assert_eq!(b2(2), census::Count { kept: 1, pruned: 0 });
+    assert_eq!(b2(3), census::Count { kept: 0, pruned: 1 });
+    assert_eq!(census.0[&4][&vec![A1, B2]].pruned, 6);
+}
+
+#[test]
+fn energy_consistency() {
+    use Irrep::*;
+    let irreps = [A1, B2];
+    let pg = PointGroup::C2v {
+        axis: Axis::Z,
+        planes: [Plane(Axis::X, Axis::Z), Plane(Axis::Y, Axis::Z)],
+    };
+    let (modchecks, eqchecks) =
+        Taylor::make_checks(irreps.iter().copied().enumerate().collect(), &pg);
+    let disps = Taylor::new(5, 2, modchecks, eqchecks).disps();
+    let mut energies: Vec<f64> = disps
+        .iter()
+        .map(|d| {
+            let (x, y) = (d[0] as f64, d[1] as f64);
+            x * x + 0.5 * y * y + x * y * y
+        })
+        .collect();
+    let got =
+        consistency::check_energies(&pg, &irreps, &disps, &energies, 1e-10);
+    assert_eq!(got, Some(vec![]));
+
+    // a broken-symmetry solution at +2 steps along the B2 coordinate
+    let i = disps.position(&[0, 2]).unwrap();
+    let j = disps.position(&[0, -2]).unwrap();
+    energies[i] += 1e-6;
+    let got =
+        consistency::check_energies(&pg, &irreps, &disps, &energies, 1e-8)
+            .unwrap();
+    assert_eq!(got.len(), 1);
+    assert_eq!((got[0].disp, got[0].image), (i.min(j), i.max(j)));
+    assert!((got[0].diff.abs() - 1e-6).abs() < 1e-12);
+}
+
+/// a nonplanar, asymmetric four-atom geometry in Ångstrom
+fn hooh() -> Vec<na::Vector3<f64>> {
+    vec![
+        na::Vector3::new(0.95, 0.1, 0.3),
+        na::Vector3::new(0.0, 0.0, 0.0),
+        na::Vector3::new(-0.2, 1.4, 0.05),
+        na::Vector3::new(-0.9, 1.6, 0.8),
+    ]
+}
+
+#[test]
+fn b_rows() {
+    use intder::Siic::*;
+    let geom = hooh();
+    let h = 1e-6;
+    for siic in [
+        Stretch(0, 1),
+        Bend(0, 1, 2),
+        Torsion(0, 1, 2, 3),
+        Lin1(0, 1, 2, 3),
+    ] {
+        let got = internals::b_row(&siic, &geom).unwrap();
+        for i in 0..3 * geom.len() {
+            let mut fwd = geom.clone();
+            fwd[i / 3][i % 3] += h;
+            let mut bwd = geom.clone();
+            bwd[i / 3][i % 3] -= h;
+            let want = (internals::value(&siic, &fwd).unwrap()
+                - internals::value(&siic, &bwd).unwrap())
+                / (2.0 * h);
+            assert!(
+                (got[i] - want).abs() < 1e-8,
+                "{siic}[{i}]: got {}, want {want}",
+                got[i]
+            );
+        }
+    }
+}
+
+#[test]
+fn harmonic_diatomic() {
+    let mh = harm::atomic_mass("H").unwrap();
+    let geom = vec![
+        na::Vector3::new(0.0, 0.0, 0.0),
+        na::Vector3::new(0.0, 0.0, 0.74),
+    ];
+    let k = 0.5;
+    let got = harm::Harmonic::from_parts(
+        &[intder::Siic::Stretch(0, 1)],
+        &[vec![1.0]],
+        &geom,
+        &[mh, mh],
+        &na::DMatrix::from_element(1, 1, k),
+        &[Irrep::Ag],
+    )
+    .unwrap();
+    let want = 2720.22864939427 * (k * 2.0 / mh).sqrt();
+    assert!((got.freqs[0] - want).abs() < 1e-8);
+    assert_eq!(got.irreps, vec![Irrep::Ag]);
+}
+
+#[test]
+fn split_disps() {
+    let taylor = Taylor::new(5, 3, None, None);
+    let (harm, rest) = taylor.split_disps();
+    assert_eq!(harm, Taylor::new(3, 3, None, None).disps());
+    assert_eq!(harm.len() + rest.len(), taylor.disps().len());
+    assert!(rest.without(&harm) == rest);
+
+    let full = taylor.disps();
+    let energies: Vec<_> = (0..full.len()).map(|i| i as f64).collect();
+    let lookup = |d: &Disps| -> Vec<f64> {
+        d.iter()
+            .map(|x| energies[full.position(x).unwrap()])
+            .collect()
+    };
+    let (he, re) = (lookup(&harm), lookup(&rest));
+    let got = full.merge_energies(&[(&harm, &he), (&rest, &re)]);
+    assert_eq!(got, Ok(energies));
+    assert_eq!(
+        full.merge_energies(&[(&harm, &he)]),
+        Err(MergeError::Missing(rest.0[0].to_vec()))
+    );
+    assert_eq!(
+        full.merge_energies(&[(&harm, &he), (&rest, &re[1..])]),
+        Err(MergeError::LengthMismatch {
+            part: 1,
+            disps: rest.len(),
+            energies: rest.len() - 1
+        })
+    );
+}
+
+#[test]
+fn extend_order() {
+    let checks = Some(Checks([vec![3], vec![], vec![]]));
+    let quartic = Taylor::new(5, 3, checks.clone(), checks.clone());
+    let sextic = Taylor::new(7, 3, checks.clone(), checks);
+    let old = quartic.disps();
+    let new = sextic.new_disps(&old);
+    let full = sextic.disps();
+    assert_eq!(old.len() + new.len(), full.len());
+    assert!(new.iter().all(|d| old.position(d).is_none()));
+
+    let energy = |d: &[i8]| d.iter().map(|&x| x as f64 * x as f64).sum();
+    let oe: Vec<f64> = old.iter().map(energy).collect();
+    let ne: Vec<f64> = new.iter().map(energy).collect();
+    let got = sextic.merge_energies((&old, &oe), (&new, &ne)).unwrap();
+    let want: Vec<f64> = full.iter().map(energy).collect();
+    assert_eq!(got, want);
+}
+
+#[test]
+fn disp_ids() {
+    let disp = vec![1, 0, -2, 4];
+    let id = disp_id(&disp);
+    assert_eq!(id, "+1,0,-2,+4");
+    assert_eq!(parse_disp_id(&id), Some(disp));
+    assert_eq!(parse_disp_id("+1,x"), None);
+
+    // the same displacement keeps its id when more coordinates are checked
+    let plain = Taylor::new(5, 3, None, None).disps();
+    let checks = Some(Checks([vec![3], vec![], vec![]]));
+    let checked = Taylor::new(5, 3, checks.clone(), checks).disps();
+    let energies: HashMap<_, _> = plain
+        .ids()
+        .into_iter()
+        .enumerate()
+        .map(|(i, id)| (id, i as f64))
+        .collect();
+    let got = checked.energies_by_id(&energies).unwrap();
+    for (disp, e) in checked.iter().zip(got) {
+        assert_eq!(&plain.0[e as usize], disp);
+    }
+    let mut partial = energies.clone();
+    partial.remove("0,0,0");
+    assert_eq!(checked.energies_by_id(&partial), Err("0,0,0".to_owned()));
+}
+
+#[test]
+fn energy_cache() {
+    let path = std::env::temp_dir().join("taylor_energy_cache.dat");
+    let _ = std::fs::remove_file(&path);
+    let quartic = Taylor::new(5, 3, None, None);
+    let disps = quartic.disps();
+    let energies: Vec<_> = (0..disps.len()).map(|i| -76.0 + i as f64).collect();
+
+    let mut c = cache::EnergyCache::open(&path).unwrap();
+    assert!(c.is_empty());
+    c.extend(42, 0.005, &disps, &energies);
+    c.save().unwrap();
+
+    let c = cache::EnergyCache::open(&path).unwrap();
+    assert_eq!(c.len(), disps.len());
+    assert_eq!(c.energies(42, 0.005, &disps), Ok(energies));
+    // different step size or reference geometry misses the cache
+    assert_eq!(c.missing(42, 0.01, &disps), disps);
+    assert_eq!(c.missing(43, 0.005, &disps), disps);
+
+    let sextic = Taylor::new(7, 3, None, None);
+    assert_eq!(
+        c.missing(42, 0.005, &sextic.disps()),
+        sextic.new_disps(&disps)
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn parallel_generation() {
+    let checks = Some(Checks([vec![5, 6, 7], vec![8], vec![9]]));
+    let serial = Taylor::new(5, 9, checks.clone(), checks.clone());
+    let par = Taylor::new_par(5, 9, checks.clone(), checks);
+    assert_eq!(par, serial);
+    assert_eq!(par.disps_par(), serial.disps());
+
+    let serial = Taylor::new(7, 4, None, None);
+    let par = Taylor::new_par(7, 4, None, None);
+    assert_eq!(par, serial);
+    assert_eq!(par.disps_par(), serial.disps());
+}
+
+#[test]
+fn many_coordinates() {
+    // m.pow(n) overflows a usize here, but the rows don't depend on it
+    let got = Taylor::new(5, 30, None, None);
+    assert_eq!(got.forces.len(), Taylor::num_rows(5, 30).unwrap());
+    assert_eq!(got.forces.len(), 46376);
+    assert_eq!(got, Taylor::new_par(5, 30, None, None));
+
+    assert_eq!(Taylor::new(1, 3, None, None).forces, vec![vec![0, 0, 0]]);
+    assert_eq!(Taylor::new(3, 0, None, None).forces, vec![Vec::<u8>::new()]);
+}
+
+#[test]
+fn infeasible_expansions() {
+    assert_eq!(
+        Taylor::try_new(200, 3, None, None),
+        Err(Error::OrderTooLarge(200))
+    );
+    assert_eq!(
+        Taylor::try_new(128, 1_000_000, None, None),
+        Err(Error::TooManyRows {
+            m: 128,
+            n: 1_000_000
+        })
+    );
+    let checks = Some(Checks([vec![4], vec![], vec![]]));
+    assert_eq!(
+        Taylor::try_new(5, 3, checks.clone(), checks),
+        Err(Error::InvalidCheck { check: 4, n: 3 })
+    );
+}
+
+#[test]
+fn force_matrix() {
+    let mut got = ForceMatrix::new(3);
+    got.try_push(&[0_usize, 1, 2]).unwrap();
+    assert!(got.try_push(&[0_usize, 300, 2]).is_err());
+    assert_eq!(got.len(), 1);
+    assert_eq!(got.as_slice(), &[0, 1, 2]);
+
+    let mut rows = Rows::from(vec![vec![1, -1], vec![0, 2], vec![1, -1]]);
+    rows.sort();
+    assert_eq!(rows, vec![vec![0, 2], vec![1, -1], vec![1, -1]]);
+    rows.dedup();
+    assert_eq!(rows, vec![vec![0, 2], vec![1, -1]]);
+    assert_eq!(rows.binary_search(&[1, -1]), Ok(1));
+    assert_eq!(rows.binary_search(&[0, 3]), Err(1));
+
+    // serialized as nested rows, like the Vec<Vec<T>> it replaced
+    let json = serde_json::to_string(&rows).unwrap();
+    assert_eq!(json, "[[0,2],[1,-1]]");
+    let got: Rows<i8> = serde_json::from_str(&json).unwrap();
+    assert_eq!(got, rows);
+    assert!(serde_json::from_str::<Rows<i8>>("[[0,2],[1]]").is_err());
+}
+
+#[test]
+fn project_round_trip() {
+    use project::{Coord, Project};
+    let pg = PointGroup::C2v {
+        axis: Axis::Z,
+        planes: [Plane(Axis::X, Axis::Z), Plane(Axis::Y, Axis::Z)],
+    };
+    let irreps = vec![(0, Irrep::A1), (1, Irrep::A1), (2, Irrep::B2)];
+    let checks = Taylor::make_checks(irreps, &pg);
+    let taylor = Taylor::new(5, 3, checks.0.clone(), checks.1.clone());
+    let disps = taylor.disps();
+    let want = Project {
+        atoms: vec!["H".to_owned(), "O".to_owned(), "H".to_owned()],
+        geom: vec![
+            [0.0, 1.431390244079, 0.986041163966],
+            [0.0, 0.0, -0.124238450265],
+            [0.0, -1.431390244079, 0.986041163966],
+        ],
+        simple_internals: vec![
+            Coord::Stretch(0, 1),
+            Coord::Stretch(1, 2),
+            Coord::Bend(0, 1, 2),
+        ],
+        symmetry_internals: vec![
+            vec![1.0, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0],
+            vec![1.0, -1.0, 0.0],
+        ],
+        point_group: pg,
+        irreps: vec![Irrep::A1, Irrep::A1, Irrep::B2],
+        checks,
+        order: 5,
+        step_size: 0.005,
+        energies: Some(vec![-76.0; disps.len()]),
+        taylor: Some(taylor),
+        disps: Some(disps),
+        fcs: Some(vec![fcs::Fc(1, 1, 0, 0, 0.5)]),
+    };
+    let path = std::env::temp_dir().join("taylor_project.json");
+    want.save(&path).unwrap();
+    let got = Project::load(&path).unwrap();
+    assert_eq!(got, want);
+
+    // a force constant past the 3 SICs, as from a mismatched fort.9903
+    let mut bad = want;
+    bad.fcs = Some(vec![fcs::Fc(4, 1, 0, 0, 0.5)]);
+    bad.save(&path).unwrap();
+    let err = Project::load(&path).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn spectro_diatomic() {
+    use spectro::CartFcs;
+    let geom = vec![
+        na::Vector3::new(0.0, 0.0, 0.0),
+        na::Vector3::new(0.0, 0.0, 0.74),
+    ];
+    let (k2, k3, k4) = (0.5, -1.2, 3.0);
+    let fcs = vec![
+        fcs::Fc(1, 1, 0, 0, k2),
+        fcs::Fc(1, 1, 1, 0, k3),
+        fcs::Fc(1, 1, 1, 1, k4),
+    ];
+    let got = CartFcs::from_parts(
+        &[intder::Siic::Stretch(0, 1)],
+        &[vec![1.0]],
+        &geom,
+        2,
+        &fcs,
+    )
+    .unwrap();
+    let a = internals::BOHR_TO_ANG;
+    // only the z coordinates (2 and 5) contribute, with a sign of -1 for the
+    // first atom
+    let sign = |i: usize| match i {
+        2 => -1.0,
+        5 => 1.0,
+        _ => 0.0,
+    };
+    for i in 0..6 {
+        for j in 0..6 {
+            let want = k2 * a * a * sign(i) * sign(j);
+            assert!((got.fc2[(i, j)] - want).abs() < 1e-12);
+        }
+    }
+    // the transverse coordinates pick up curvature terms in the cubic force
+    // constants, so only check the ones along the bond
+    for i in [2, 5] {
+        for j in [2, 5] {
+            for k in [2, 5] {
+                let want = k3 * a.powi(3) * sign(i) * sign(j) * sign(k);
+                assert!((got.fc3[36 * i + 6 * j + k] - want).abs() < 1e-12);
+            }
+        }
+    }
+    assert_eq!(got.unique_fc3().len(), 56);
+    assert_eq!(got.unique_fc4().len(), 126);
+    let last = *got.unique_fc4().last().unwrap();
+    assert!((last - k4 * a.powi(4)).abs() < 1e-12);
+
+    let dir = std::env::temp_dir().join("taylor_spectro");
+    std::fs::create_dir_all(&dir).unwrap();
+    got.write_spectro(&dir).unwrap();
+    let fort15 = std::fs::read_to_string(dir.join("fort.15")).unwrap();
+    let mut lines = fort15.lines();
+    assert_eq!(lines.next(), Some("    2   12"));
+    assert_eq!(lines.count(), 12);
+    let fort30 = std::fs::read_to_string(dir.join("fort.30")).unwrap();
+    assert_eq!(fort30.lines().next(), Some("    2   56"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cartesian_transform() {
+    use intder::Siic::*;
+    let geom = hooh();
+    let siics = [
+        Stretch(0, 1),
+        Stretch(1, 2),
+        Stretch(2, 3),
+        Bend(0, 1, 2),
+        Bend(1, 2, 3),
+        Torsion(0, 1, 2, 3),
+    ];
+    let sics = vec![
+        vec![1.0, 0.0, 1.0],
+        vec![0.0, 1.0],
+        vec![1.0, 0.0, -1.0],
+        vec![0.0, 0.0, 0.0, 1.0, 1.0],
+        vec![0.0, 0.0, 0.0, 1.0, -1.0],
+        vec![0.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+    ];
+    let nsic = sics.len();
+    let fcs = vec![
+        fcs::Fc(1, 1, 0, 0, 8.1),
+        fcs::Fc(2, 1, 0, 0, 0.3),
+        fcs::Fc(2, 2, 0, 0, 4.5),
+        fcs::Fc(3, 3, 0, 0, 7.9),
+        fcs::Fc(4, 4, 0, 0, 0.8),
+        fcs::Fc(5, 4, 0, 0, -0.1),
+        fcs::Fc(5, 5, 0, 0, 0.7),
+        fcs::Fc(6, 6, 0, 0, 0.05),
+        fcs::Fc(1, 1, 1, 0, -40.0),
+        fcs::Fc(4, 2, 1, 0, 0.4),
+        fcs::Fc(6, 6, 2, 0, -0.2),
+        fcs::Fc(3, 3, 1, 0, -38.0),
+        fcs::Fc(1, 1, 1, 1, 180.0),
+        fcs::Fc(4, 4, 3, 3, 0.9),
+        fcs::Fc(6, 6, 6, 6, 0.02),
+    ];
+    let f2 = transform::sic_tensor(&fcs, nsic, 2);
+    let f3 = transform::sic_tensor(&fcs, nsic, 3);
+    let f4 = transform::sic_tensor(&fcs, nsic, 4);
+    let u = internals::u_matrix(&sics, siics.len());
+    let sic_values = |g: &[na::Vector3<f64>]| {
+        let v = na::DVector::from_iterator(
+            siics.len(),
+            siics.iter().map(|s| internals::value(s, g).unwrap()),
+        );
+        &u * v
+    };
+    let s0 = sic_values(&geom);
+
+    // the analytic Cartesian gradient of the SIC polynomial
+    let n = 3 * geom.len();
+    let grad = |steps: &[(usize, f64)]| -> na::DVector<f64> {
+        let mut g = geom.clone();
+        for &(i, h) in steps {
+            g[i / 3][i % 3] += h;
+        }
+        let ds = sic_values(&g) - &s0;
+        let mut de = na::DVector::zeros(nsic);
+        for a in 0..nsic {
+            for b in 0..nsic {
+                de[a] += f2[a * nsic + b] * ds[b];
+                for c in 0..nsic {
+                    let abc = (a * nsic + b) * nsic + c;
+                    de[a] += f3[abc] * ds[b] * ds[c] / 2.0;
+                    for d in 0..nsic {
+                        de[a] +=
+                            f4[abc * nsic + d] * ds[b] * ds[c] * ds[d] / 6.0;
+                    }
+                }
+            }
+        }
+        (internals::sym_b_matrix(&siics, &sics, &g)
+            .unwrap()
+            .transpose()
+            * de)
+            .rows(0, n)
+            .into_owned()
+    };
+
+    let derivs =
+        transform::SicDerivs::new(&siics, &sics, &geom, geom.len()).unwrap();
+    let got = transform::to_cartesian(&fcs, &derivs);
+
+    let h = 1e-4;
+    for j in 0..n {
+        let want = (grad(&[(j, h)]) - grad(&[(j, -h)])) / (2.0 * h);
+        for i in 0..n {
+            let diff = (got.fc2[i * n + j] - want[i]).abs();
+            assert!(diff < 1e-6, "fc2[{i},{j}] off by {diff}");
+        }
+    }
+
+    let h = 1e-3;
+    for j in 0..n {
+        for k in 0..n {
+            let want = (grad(&[(j, h), (k, h)])
+                - grad(&[(j, h), (k, -h)])
+                - grad(&[(j, -h), (k, h)])
+                + grad(&[(j, -h), (k, -h)]))
+                / (4.0 * h * h);
+            for i in 0..n {
+                let diff = (got.fc3[(i * n + j) * n + k] - want[i]).abs();
+                assert!(diff < 1e-4, "fc3[{i},{j},{k}] off by {diff}");
+            }
+        }
+    }
+
+    let h = 1e-3;
+    for j in 0..n {
+        for k in 0..n {
+            for l in (0..n).filter(|l| (j + k + l) % 5 == 0) {
+                let mut want = na::DVector::zeros(n);
+                for sj in [-1.0, 1.0] {
+                    for sk in [-1.0, 1.0] {
+                        for sl in [-1.0, 1.0] {
+                            want += sj
+                                * sk
+                                * sl
+                                * grad(&[
+                                    (j, sj * h),
+                                    (k, sk * h),
+                                    (l, sl * h),
+                                ]);
+                        }
+                    }
+                }
+                want /= 8.0 * h * h * h;
+                for i in 0..n {
+                    let got = got.fc4[((i * n + j) * n + k) * n + l];
+                    let diff = (got - want[i]).abs();
+                    assert!(
+                        diff < 1e-3 * got.abs().max(1.0),
+                        "fc4[{i},{j},{k},{l}] off by {diff}: {got} vs {}",
+                        want[i]
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// the step size of [quartic_surface]
+const FIT_STEP: f64 = 0.01;
+
+/// the energy of a quartic polynomial in 3 coordinates at the displacement
+/// `d`, in units of [FIT_STEP]
+fn quartic_energy(d: &[i8]) -> f64 {
+    let x: Vec<_> = d.iter().map(|&i| i as f64 * FIT_STEP).collect();
+    0.5 * x[0] * x[0] + 0.3 * x[1] * x[1] + 0.1 * x[0] * x[1] * x[2]
+        - 0.05 * x[2].powi(4)
+}
+
+/// an unpruned quartic expansion in 3 coordinates, its displacements, and the
+/// [quartic_energy] at each of them
+fn quartic_surface() -> (Taylor, Disps, Vec<f64>) {
+    let taylor = Taylor::new(5, 3, None, None);
+    let disps = taylor.disps();
+    let energies = disps.iter().map(quartic_energy).collect();
+    (taylor, disps, energies)
+}
+
+#[test]
+fn fit_diagnostics() {
+    let (taylor, disps, mut energies) = quartic_surface();
+    let step = FIT_STEP;
+
+    let fit = fit::Fit::new(&taylor.to_anpass(&disps, &energies, step));
+    assert_eq!(fit.rank, taylor.forces.len());
+    assert!(fit.rms() < 1e-12);
+    assert!(fit.outliers(3.0).is_empty());
+
+    let bad = disps.position(&[2, -1, 1]).unwrap();
+    energies[bad] += 1e-5;
+    let fit = fit::Fit::new(&taylor.to_anpass(&disps, &energies, step));
+    assert_eq!(fit.outliers(3.0), vec![bad]);
+    let flagged = fit.flagged(&disps, 3.0);
+    assert_eq!(flagged.iter().collect::<Vec<_>>(), vec![&[2, -1, 1][..]]);
+}
+
+#[test]
+fn weighted_fit() {
+    use fit::{Fit, Weighting};
+    let (taylor, disps, energies) = quartic_surface();
+    let step = FIT_STEP;
+    let anpass = taylor.to_anpass(&disps, &energies, step);
+
+    // an exact polynomial is recovered by every weighting
+    for weighting in [Weighting::Boltzmann(1e-4), Weighting::Distance(0.01)] {
+        let fit = Fit::weighted(&anpass, weighting);
+        assert!(fit.rms() < 1e-12);
+    }
+    let mut fcs = Fit::new(&anpass).force_constants(&taylor.forces);
+    fcs.retain(|fc| fc.4.abs() > 1e-8);
+    let want = [
+        fcs::Fc(3, 3, 3, 3, -1.2),
+        fcs::Fc(3, 2, 1, 0, 0.1),
+        fcs::Fc(2, 2, 0, 0, 0.6),
+        fcs::Fc(1, 1, 0, 0, 1.0),
+    ];
+    assert_eq!(fcs.len(), want.len());
+    for fc in want {
+        let got = fcs.iter().find(|g| g.indices() == fc.indices()).unwrap();
+        assert!((got.4 - fc.4).abs() < 1e-6, "{got:?} != {fc:?}");
+    }
+
+    // a sextic term outside the expansion is absorbed away from the origin
+    // when the distant points are down-weighted
+    let energies: Vec<_> = disps
+        .iter()
+        .map(|d| quartic_energy(d) + 1e3 * (d[0] as f64 * step).powi(6))
+        .collect();
+    let anpass = taylor.to_anpass(&disps, &energies, step);
+    let origin = disps.position(&[0, 0, 0]).unwrap();
+    let equal = Fit::new(&anpass).residuals[origin].abs();
+    for weighting in [Weighting::Boltzmann(1e-5), Weighting::Distance(0.01)] {
+        let fit = Fit::weighted(&anpass, weighting);
+        assert!(fit.residuals[origin].abs() < equal);
+    }
+}
+
+#[test]
+fn cross_validation() {
+    // a quartic polynomial sampled with the sextic displacements
+    let taylor = Taylor::new(7, 2, None, None);
+    let disps = taylor.disps();
+    let step = 0.01;
+    let energies: Vec<_> = disps
+        .iter()
+        .map(|d| {
+            let x = d[0] as f64 * step;
+            let y = d[1] as f64 * step;
+            0.5 * x * x + 0.2 * y * y - 0.3 * x * x * y + 0.7 * y.powi(4)
+        })
+        .collect();
+    let scores = cv::select_order(
+        &[3, 5, 7],
+        2,
+        &(None, None),
+        &disps,
+        &energies,
+        step,
+        fit::Weighting::Equal,
+        Some(4),
+    )
+    .unwrap();
+    let (quad, quart, sext) = (&scores[0].1, &scores[1].1, &scores[2].1);
+    assert_eq!(quart.nterms, 15);
+    // the quadratic expansion is missing terms, while the quartic and sextic
+    // ones are exact
+    assert!(quad.loo > 1e-8);
+    assert!(quart.loo < 1e-12);
+    assert!(quart.kfold.unwrap() < 1e-12);
+    assert!(quad.kfold.unwrap() > 1e-8);
+    assert!(sext.rms < 1e-12);
+
+    // dropping a needed term shows up in the error
+    let fewer = taylor.without_terms(&[vec![0, 4]]);
+    assert_eq!(fewer.forces.len(), taylor.forces.len() - 1);
+    let score = cv::Score::new(
+        &Taylor::new(5, 2, None, None).without_terms(&[vec![0, 4]]),
+        &disps,
+        &energies,
+        step,
+        fit::Weighting::Equal,
+        None,
+    )
+    .unwrap();
+    assert!(score.loo > 1e-8);
+
+    let score = cv::Score::new(
+        &Taylor::new(5, 2, None, None),
+        &disps,
+        &energies,
+        step,
+        fit::Weighting::Equal,
+        Some(1),
+    );
+    assert_eq!(score, Err(cv::Error::TooFewFolds(1)));
+}
+
+#[test]
+fn fitted_taylor() {
+    let taylor = Taylor::new(5, 2, None, None);
+    let disps = taylor.disps();
+    let step = 0.01;
+    let pes = |x: f64, y: f64| {
+        0.5 * x * x + 0.2 * y * y - 0.3 * x * x * y + 0.7 * y.powi(4)
+    };
+    let energies: Vec<_> = disps
+        .iter()
+        .map(|d| pes(d[0] as f64 * step, d[1] as f64 * step))
+        .collect();
+    let fit = fit::Fit::new(&taylor.to_anpass(&disps, &energies, step));
+    let fitted = fit.fitted(&taylor.forces);
+    let from_fcs =
+        fit::FittedTaylor::from_fcs(&fit.force_constants(&taylor.forces), 2);
+
+    let (x, y): (f64, f64) = (0.13, -0.07);
+    let grad = [x - 0.6 * x * y, 0.4 * y - 0.3 * x * x + 2.8 * y.powi(3)];
+    let hess = [[1.0 - 0.6 * y, -0.6 * x], [-0.6 * x, 0.4 + 8.4 * y * y]];
+    for f in [&fitted, &from_fcs] {
+        assert!((f.energy(&[x, y]) - pes(x, y)).abs() < 1e-10);
+        let g = f.gradient(&[x, y]);
+        let h = f.hessian(&[x, y]);
+        for i in 0..2 {
+            assert!((g[i] - grad[i]).abs() < 1e-10);
+            for j in 0..2 {
+                assert!((h[(i, j)] - hess[i][j]).abs() < 1e-10);
+            }
+        }
+    }
+
+    // a double well with minima at ±0.5 and a barrier at the origin
+    let well =
+        fit::FittedTaylor::new(vec![vec![2], vec![4]].into(), vec![-0.5, 1.0]);
+    let min = well.stationary_point(&[0.4], 1e-12, 50).unwrap();
+    assert!((min[0] - 0.5).abs() < 1e-10);
+    assert!(well.hessian(min.as_slice())[(0, 0)] > 0.0);
+    let ts = well.stationary_point(&[0.05], 1e-12, 50).unwrap();
+    assert!(ts[0].abs() < 1e-10);
+    assert!(well.hessian(ts.as_slice())[(0, 0)] < 0.0);
+}
+
+#[test]
+fn pes_cuts() {
+    use cuts::{Cut, Format};
+    let taylor = Taylor::new(5, 2, None, None);
+    let disps = taylor.disps();
+    let step = 0.01;
+    let energies: Vec<_> = disps
+        .iter()
+        .map(|d| {
+            let (x, y) = (d[0] as f64 * step, d[1] as f64 * step);
+            0.5 * x * x + 0.2 * y * y - 0.3 * x * x * y
+        })
+        .collect();
+    let fit = fit::Fit::new(&taylor.to_anpass(&disps, &energies, step));
+    let fitted = fit.fitted(&taylor.forces);
+
+    let cut =
+        Cut::new(&[1], &disps, Some(&energies), Some(&fitted), step, None);
+    let hw = disps
+        .iter()
+        .filter(|d| d[0] == 0)
+        .map(|d| d[1])
+        .max()
+        .unwrap();
+    assert_eq!(cut.points.len(), 2 * hw as usize + 1);
+    for p in &cut.points {
+        let want = 0.2 * p.x[0] * p.x[0];
+        assert!((p.fitted.unwrap() - want).abs() < 1e-12);
+        if let Some(e) = p.computed {
+            assert!((e - want).abs() < 1e-12);
+        }
+    }
+    let mut csv = Vec::new();
+    cut.write(&mut csv, Format::Csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(csv.lines().next(), Some("S2,fitted,computed"));
+    assert_eq!(csv.lines().count(), cut.points.len() + 1);
+
+    let cut = Cut::new(&[0, 1], &disps, Some(&energies), None, step, Some(2));
+    assert_eq!(cut.points.len(), 25);
+    assert_eq!(cut.points[6].x, vec![-0.01, -0.01]);
+    assert_eq!(
+        cut.points[6].computed,
+        disps.position(&[-1, -1]).map(|i| energies[i])
+    );
+    let mut dat = Vec::new();
+    cut.write(&mut dat, Format::Gnuplot).unwrap();
+    let dat = String::from_utf8(dat).unwrap();
+    assert_eq!(dat.lines().filter(|l| l.is_empty()).count(), 4);
+    assert!(dat.lines().nth(1).unwrap().contains('?'));
+}
+
+#[test]
+fn sic_completeness() {
+    use intder::Siic::*;
+    use internals::{check_sics, SicProblem};
+    let water = vec![
+        na::Vector3::new(0.0, 0.757, 0.587),
+        na::Vector3::new(0.0, 0.0, 0.0),
+        na::Vector3::new(0.0, -0.757, 0.587),
+    ];
+    let siics = [Stretch(0, 1), Stretch(1, 2), Bend(0, 1, 2)];
+    let sics = vec![vec![1.0, 1.0], vec![0.0, 0.0, 1.0], vec![1.0, -1.0]];
+    assert!(check_sics(&siics, &sics, &water, 3).unwrap().is_empty());
+    assert_eq!(
+        check_sics(&siics, &sics[..2], &water, 3).unwrap(),
+        vec![SicProblem::Incomplete(2, 3)]
+    );
+    let redundant = vec![
+        vec![1.0, 1.0],
+        vec![1.0],
+        vec![0.0, 1.0],
+        vec![0.0, 0.0, 1.0],
+    ];
+    assert_eq!(
+        check_sics(&siics, &redundant, &water, 3).unwrap(),
+        vec![SicProblem::Redundant(2)]
+    );
+
+    let siics = [Stretch(0, 1), Stretch(1, 2), Stretch(2, 3), Bend(0, 1, 2)];
+    let sics = vec![vec![1.0], vec![0.0, 1.0], vec![0.0, 0.0, 1.0]];
+    assert_eq!(
+        check_sics(&siics, &sics, &hooh(), 4).unwrap(),
+        vec![SicProblem::Incomplete(3, 6)]
+    );
+
+    let co2 = vec![
+        na::Vector3::new(0.0, 0.0, -1.16),
+        na::Vector3::new(0.0, 0.0, 0.0),
+        na::Vector3::new(0.0, 0.0, 1.16),
+    ];
+    assert!(internals::is_linear(&co2, 3));
+    assert!(!internals::is_linear(&water, 3));
+    assert_eq!(
+        check_sics(
+            &[Stretch(0, 1), Stretch(1, 2)],
+            &[vec![1.0], vec![0.0, 1.0]],
+            &co2,
+            3
+        )
+        .unwrap(),
+        vec![SicProblem::Incomplete(2, 4)]
+    );
+
+    assert_eq!(
+        check_sics(&[Out(0, 1, 2, 3)], &[vec![1.0]], &hooh(), 4),
+        Err(internals::Unsupported(Out(0, 1, 2, 3)))
+    );
+}
+
+#[test]
+fn generate_sics() {
+    use intder::Siic::*;
+    let labels = |l: &[&str]| -> Vec<String> {
+        l.iter().map(|s| s.to_string()).collect()
+    };
+    let water = vec![
+        na::Vector3::new(0.0, 0.757, 0.587),
+        na::Vector3::new(0.0, 0.0, 0.0),
+        na::Vector3::new(0.0, -0.757, 0.587),
+    ];
+    let (siics, sics) =
+        generate::generate(&labels(&["H", "O", "H"]), &water, 3, 1e-6).unwrap();
+    assert_eq!(siics, vec![Stretch(0, 1), Stretch(1, 2), Bend(0, 1, 2)]);
+    assert_eq!(
+        sics,
+        vec![
+            vec![1.0, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0],
+            vec![1.0, -1.0, 0.0]
+        ]
+    );
+
+    // C2 HOOH needs a torsion
+    let hooh = vec![
+        na::Vector3::new(0.9, 0.9, 0.3),
+        na::Vector3::new(0.7, 0.0, 0.0),
+        na::Vector3::new(-0.7, 0.0, 0.0),
+        na::Vector3::new(-0.9, -0.9, 0.3),
+    ];
+    let (siics, sics) =
+        generate::generate(&labels(&["H", "O", "O", "H"]), &hooh, 4, 1e-6)
+            .unwrap();
+    assert_eq!(sics.len(), 6);
+    assert!(siics.contains(&Torsion(0, 1, 2, 3)));
+    assert!(internals::check_sics(&siics, &sics, &hooh, 4)
+        .unwrap()
+        .is_empty());
+
+    // planar formaldehyde needs an out-of-plane coordinate
+    let h2co = vec![
+        na::Vector3::new(0.0, 0.0, 0.0),
+        na::Vector3::new(0.0, 0.0, 1.2),
+        na::Vector3::new(0.0, 0.94, -0.54),
+        na::Vector3::new(0.0, -0.94, -0.54),
+    ];
+    let (siics, sics) =
+        generate::generate(&labels(&["C", "O", "H", "H"]), &h2co, 4, 1e-6)
+            .unwrap();
+    assert_eq!(sics.len(), 6);
+    assert!(siics.iter().any(|s| matches!(s, Torsion(..))));
+    assert!(internals::check_sics(&siics, &sics, &h2co, 4)
+        .unwrap()
+        .is_empty());
+
+    // linear CO2 uses LIN1 coordinates against the dummy atoms
+    let co2 = vec![
+        na::Vector3::new(0.0, 0.0, -1.16),
+        na::Vector3::new(0.0, 0.0, 0.0),
+        na::Vector3::new(0.0, 0.0, 1.16),
+        na::Vector3::new(1.0, 0.0, 0.0),
+        na::Vector3::new(0.0, 1.0, 0.0),
+    ];
+    let (siics, sics) =
+        generate::generate(&labels(&["O", "C", "O"]), &co2, 3, 1e-6).unwrap();
+    assert_eq!(sics.len(), 4);
+    assert!(siics.contains(&Lin1(0, 1, 2, 3)));
+    assert!(siics.contains(&Lin1(0, 1, 2, 4)));
+    assert!(internals::check_sics(&siics, &sics, &co2, 3)
+        .unwrap()
+        .is_empty());
+
+    assert_eq!(
+        generate::generate(&labels(&["Kr", "F"]), &co2[..2], 2, 1e-6),
+        Err(generate::Error::NoRadius("Kr".to_owned()))
+    );
+}
+
+#[test]
+fn ordered_checks() {
+    use Irrep::*;
+    let pg = PointGroup::C2v {
+        axis: Axis::Z,
+        planes: [Plane(Axis::X, Axis::Z), Plane(Axis::Y, Axis::Z)],
+    };
+    let irreps = [B2, A1, B1];
+    let checks = |perm: &[usize]| {
+        let irreps = perm.iter().map(|&i| irreps[i]).enumerate().collect();
+        Taylor::make_checks(irreps, &pg).0.unwrap()
+    };
+
+    // sorting by irrep moves the B2 SIC from position 1 to 2 and the checks
+    // follow it
+    let perm = ordering::permutation(&irreps, None, true).unwrap();
+    assert_eq!(perm, vec![1, 0, 2]);
+    assert_eq!(checks(&perm), Checks([vec![2], vec![3], vec![]]));
+
+    let perm = ordering::permutation(&irreps, Some(&[3, 1, 2]), true).unwrap();
+    assert_eq!(perm, vec![2, 0, 1]);
+    assert_eq!(checks(&perm), Checks([vec![2], vec![1], vec![]]));
+
+    let perm = ordering::permutation(&irreps, None, false).unwrap();
+    assert_eq!(checks(&perm), Checks([vec![1], vec![3], vec![]]));
+
+    assert_eq!(
+        ordering::permutation(&irreps, Some(&[1, 1, 2]), true),
+        Err(ordering::Error::NotPermutation(3))
+    );
+    let mut sics = vec![vec![1.0, 1.0], vec![1.0, -1.0]];
+    ordering::negate(&mut sics, &[2]).unwrap();
+    assert_eq!(sics, vec![vec![1.0, 1.0], vec![-1.0, 1.0]]);
+    assert_eq!(
+        ordering::negate(&mut sics, &[0]),
+        Err(ordering::Error::OutOfRange { sic: 0, n: 2 })
+    );
+}
@@ -0,0 +1,332 @@
+//! nonlinear transformation of quadratic, cubic, and quartic force constants in
+//! symmetry internal coordinates to Cartesian coordinates. see W. D. Allen and
+//! A. G. Császár, J. Chem. Phys. 98, 1993, 2983-3015 for the general
+//! expressions
+
+use intder::Siic;
+use nalgebra as na;
+
+use crate::{
+    fcs::Fc,
+    internals::{b_row, u_matrix, Unsupported},
+};
+
+/// displacement in Ångstrom for the finite differences of the B-matrix giving
+/// the second derivatives of the internal coordinates
+const H2: f64 = 1e-4;
+
+/// displacement in Ångstrom for the second differences of the B-matrix giving
+/// the third derivatives of the internal coordinates
+const H3: f64 = 1e-3;
+
+/// the first three Cartesian derivatives of a set of SICs. only the
+/// derivatives with respect to the real atoms are included
+#[derive(Clone, Debug, PartialEq)]
+pub struct SicDerivs {
+    /// the B-matrix, nsic x ncart
+    pub b1: na::DMatrix<f64>,
+    /// the second derivatives of each SIC, ncart x ncart
+    pub b2: Vec<na::DMatrix<f64>>,
+    /// the third derivatives of each SIC, as full ncart³ tensors in row-major
+    /// order
+    pub b3: Vec<Vec<f64>>,
+}
+
+/// return the indices of the atoms involved in `siic`
+fn siic_atoms(siic: &Siic) -> Vec<usize> {
+    match *siic {
+        Siic::Stretch(a, b) => vec![a, b],
+        Siic::Bend(a, b, c) => vec![a, b, c],
+        Siic::Torsion(a, b, c, d)
+        | Siic::Lin1(a, b, c, d)
+        | Siic::Out(a, b, c, d)
+        | Siic::Linx(a, b, c, d)
+        | Siic::Liny(a, b, c, d) => vec![a, b, c, d],
+    }
+}
+
+/// return the indices of the Cartesian coordinates of the real atoms, the first
+/// `natoms`, that `siic` depends on
+fn active_coords(siic: &Siic, natoms: usize) -> Vec<usize> {
+    let mut ret: Vec<_> = siic_atoms(siic)
+        .into_iter()
+        .filter(|&a| a < natoms)
+        .flat_map(|a| [3 * a, 3 * a + 1, 3 * a + 2])
+        .collect();
+    ret.sort();
+    ret.dedup();
+    ret
+}
+
+/// return `geom` with each Cartesian coordinate `i` in `steps` displaced by
+/// the corresponding `h`
+fn displace(
+    geom: &[na::Vector3<f64>],
+    steps: &[(usize, f64)],
+) -> Vec<na::Vector3<f64>> {
+    let mut ret = geom.to_vec();
+    for &(i, h) in steps {
+        ret[i / 3][i % 3] += h;
+    }
+    ret
+}
+
+impl SicDerivs {
+    /// compute the derivatives of the SICs `sics`, built from the simple
+    /// internals `siics`, at `geom` in Ångstrom. the first `natoms` atoms of
+    /// `geom` are real, and any remaining dummy atoms are held fixed. the
+    /// higher derivatives come from finite differences of the analytic
+    /// B-matrix. returns an error if any of `siics` is not supported
+    pub fn new(
+        siics: &[Siic],
+        sics: &[Vec<f64>],
+        geom: &[na::Vector3<f64>],
+        natoms: usize,
+    ) -> Result<Self, Unsupported> {
+        let n = 3 * natoms;
+        let nsiic = siics.len();
+        let mut sb1 = na::DMatrix::zeros(nsiic, n);
+        let mut sb2 = Vec::with_capacity(nsiic);
+        let mut sb3 = Vec::with_capacity(nsiic);
+        let row = |siic, g: &[na::Vector3<f64>]| {
+            b_row(siic, g).map(|r| r.rows(0, n).into_owned())
+        };
+        for (s, siic) in siics.iter().enumerate() {
+            sb1.set_row(s, &row(siic, geom)?.transpose());
+            let active = active_coords(siic, natoms);
+
+            let mut b2 = na::DMatrix::zeros(n, n);
+            for &j in &active {
+                let fwd = row(siic, &displace(geom, &[(j, H2)]))?;
+                let bwd = row(siic, &displace(geom, &[(j, -H2)]))?;
+                let d = (fwd - bwd) / (2.0 * H2);
+                for &i in &active {
+                    b2[(i, j)] = d[i];
+                }
+            }
+            sb2.push((&b2 + b2.transpose()) / 2.0);
+
+            let mut b3 = vec![0.0; n * n * n];
+            for (jj, &j) in active.iter().enumerate() {
+                for &k in &active[jj..] {
+                    let pp = row(siic, &displace(geom, &[(j, H3), (k, H3)]))?;
+                    let pm = row(siic, &displace(geom, &[(j, H3), (k, -H3)]))?;
+                    let mp = row(siic, &displace(geom, &[(j, -H3), (k, H3)]))?;
+                    let mm = row(siic, &displace(geom, &[(j, -H3), (k, -H3)]))?;
+                    let d = (pp - pm - mp + mm) / (4.0 * H3 * H3);
+                    for &i in &active {
+                        b3[(i * n + j) * n + k] = d[i];
+                        b3[(i * n + k) * n + j] = d[i];
+                    }
+                }
+            }
+            sb3.push(symmetrize3(&b3, n));
+        }
+
+        let u = u_matrix(sics, nsiic);
+        let b1 = &u * sb1;
+        let mut b2 = Vec::with_capacity(sics.len());
+        let mut b3 = Vec::with_capacity(sics.len());
+        for a in 0..sics.len() {
+            let mut m2 = na::DMatrix::zeros(n, n);
+            let mut m3 = vec![0.0; n * n * n];
+            for s in 0..nsiic {
+                let c = u[(a, s)];
+                if c == 0.0 {
+                    continue;
+                }
+                m2 += c * &sb2[s];
+                for (x, y) in m3.iter_mut().zip(&sb3[s]) {
+                    *x += c * y;
+                }
+            }
+            b2.push(m2);
+            b3.push(m3);
+        }
+        Ok(Self { b1, b2, b3 })
+    }
+}
+
+/// average the rank-3 tensor `t` over all permutations of its indices
+fn symmetrize3(t: &[f64], n: usize) -> Vec<f64> {
+    let mut ret = vec![0.0; t.len()];
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                let at = |a: usize, b: usize, c: usize| t[(a * n + b) * n + c];
+                ret[(i * n + j) * n + k] = (at(i, j, k)
+                    + at(i, k, j)
+                    + at(j, i, k)
+                    + at(j, k, i)
+                    + at(k, i, j)
+                    + at(k, j, i))
+                    / 6.0;
+            }
+        }
+    }
+    ret
+}
+
+/// build the full, symmetric SIC force constant tensor of `order` from the
+/// unique entries in `fcs`
+pub(crate) fn sic_tensor(fcs: &[Fc], nsic: usize, order: usize) -> Vec<f64> {
+    let mut ret = vec![0.0; nsic.pow(order as u32)];
+    for fc in fcs.iter().filter(|fc| fc.order() == order) {
+        let idx: Vec<_> = fc.indices().iter().map(|i| i - 1).collect();
+        for perm in permutations(&idx) {
+            ret[flat_index(&perm, nsic)] = fc.4;
+        }
+    }
+    ret
+}
+
+/// return all of the orderings of `idx`, including repeats when `idx` has
+/// duplicate entries
+pub(crate) fn permutations(idx: &[usize]) -> Vec<Vec<usize>> {
+    if idx.len() <= 1 {
+        return vec![idx.to_vec()];
+    }
+    let mut ret = Vec::new();
+    for i in 0..idx.len() {
+        let mut rest = idx.to_vec();
+        let first = rest.remove(i);
+        for mut p in permutations(&rest) {
+            p.insert(0, first);
+            ret.push(p);
+        }
+    }
+    ret
+}
+
+/// the row-major index of `idx` in a tensor with every dimension of size `n`
+pub(crate) fn flat_index(idx: &[usize], n: usize) -> usize {
+    idx.iter().fold(0, |acc, i| acc * n + i)
+}
+
+/// transform every index of the rank-`rank` tensor `t`, with dimensions of
+/// size `b.nrows()`, by `b`, giving a tensor with dimensions of size
+/// `b.ncols()`. each pass contracts the leading index and moves the new
+/// index to the end, so after `rank` passes the indices are back in order
+pub(crate) fn transform_tensor(
+    t: &[f64],
+    rank: usize,
+    b: &na::DMatrix<f64>,
+) -> Vec<f64> {
+    let (nin, nout) = b.shape();
+    let mut t = t.to_vec();
+    // number of entries in the trailing, untransformed dimensions
+    let mut rest = nin.pow(rank as u32 - 1);
+    let mut done = 1;
+    for _ in 0..rank {
+        let mut next = vec![0.0; rest * done * nout];
+        for a in 0..nin {
+            for r in 0..rest * done {
+                let v = t[a * rest * done + r];
+                if v == 0.0 {
+                    continue;
+                }
+                for i in 0..nout {
+                    next[r * nout + i] += v * b[(a, i)];
+                }
+            }
+        }
+        t = next;
+        done *= nout;
+        rest /= nin;
+    }
+    t
+}
+
+/// Cartesian force constants in the units of the SIC force constants, as
+/// full, symmetric, row-major tensors
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cartesian {
+    pub fc2: Vec<f64>,
+    pub fc3: Vec<f64>,
+    pub fc4: Vec<f64>,
+}
+
+/// transform the SIC force constants in `fcs`, including any first-order
+/// entries for a non-stationary reference, to Cartesian coordinates using the
+/// SIC derivatives in `d`. the gradient contribution to the quartic force
+/// constants, which would require fourth derivatives of the SICs, is omitted
+pub fn to_cartesian(fcs: &[Fc], d: &SicDerivs) -> Cartesian {
+    let (s, n) = d.b1.shape();
+    let f1 = sic_tensor(fcs, s, 1);
+    let f2 = sic_tensor(fcs, s, 2);
+    let f3 = sic_tensor(fcs, s, 3);
+    let f4 = sic_tensor(fcs, s, 4);
+
+    // the terms in the B-matrix alone
+    let mut fc2 = transform_tensor(&f2, 2, &d.b1);
+    let mut fc3 = transform_tensor(&f3, 3, &d.b1);
+    let mut fc4 = transform_tensor(&f4, 4, &d.b1);
+
+    let f2m = na::DMatrix::from_row_slice(s, s, &f2);
+    // y[(a, k)] = F_ab B^b_k
+    let y = &f2m * &d.b1;
+    // z[a] = F_ab B^b_kl
+    let z: Vec<na::DMatrix<f64>> = (0..s)
+        .map(|a| {
+            let mut m = na::DMatrix::zeros(n, n);
+            for b in 0..s {
+                if f2m[(a, b)] != 0.0 {
+                    m += f2m[(a, b)] * &d.b2[b];
+                }
+            }
+            m
+        })
+        .collect();
+    // x[a] = F_abc B^b_k B^c_l
+    let x: Vec<na::DMatrix<f64>> = (0..s)
+        .map(|a| {
+            let fa = na::DMatrix::from_row_slice(
+                s,
+                s,
+                &f3[a * s * s..(a + 1) * s * s],
+            );
+            d.b1.transpose() * fa * &d.b1
+        })
+        .collect();
+
+    let idx2 = |i: usize, j: usize| i * n + j;
+    let idx3 = |i: usize, j: usize, k: usize| (i * n + j) * n + k;
+
+    for a in 0..s {
+        let b2 = &d.b2[a];
+        let b3 = &d.b3[a];
+        for i in 0..n {
+            for j in 0..n {
+                fc2[idx2(i, j)] += f1[a] * b2[(i, j)];
+                for k in 0..n {
+                    fc3[idx3(i, j, k)] += b2[(i, j)] * y[(a, k)]
+                        + b2[(i, k)] * y[(a, j)]
+                        + b2[(j, k)] * y[(a, i)]
+                        + f1[a] * b3[idx3(i, j, k)];
+                    for l in 0..n {
+                        let pair = |p: (usize, usize), q: (usize, usize)| {
+                            b2[p] * x[a][q]
+                        };
+                        let quad = |p: (usize, usize), q: (usize, usize)| {
+                            b2[p] * z[a][q]
+                        };
+                        fc4[idx3(i, j, k) * n + l] += pair((i, j), (k, l))
+                            + pair((i, k), (j, l))
+                            + pair((i, l), (j, k))
+                            + pair((j, k), (i, l))
+                            + pair((j, l), (i, k))
+                            + pair((k, l), (i, j))
+                            + quad((i, j), (k, l))
+                            + quad((i, k), (j, l))
+                            + quad((i, l), (j, k))
+                            + b3[idx3(i, j, k)] * y[(a, l)]
+                            + b3[idx3(i, j, l)] * y[(a, k)]
+                            + b3[idx3(i, k, l)] * y[(a, j)]
+                            + b3[idx3(j, k, l)] * y[(a, i)];
+                    }
+                }
+            }
+        }
+    }
+    Cartesian { fc2, fc3, fc4 }
+}
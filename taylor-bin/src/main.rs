@@ -1,42 +1,28 @@
+use std::path::Path;
+
 use clap::Parser;
 use intder::Intder;
 use symm::{Atom, Molecule, Pg};
-use taylor::Taylor;
-
-// borrowed from summarize-bin
-fn irrep(ir: &symm::Irrep) -> &'static str {
-    match ir {
-        symm::Irrep::A => "a",
-        symm::Irrep::B => "b",
-        symm::Irrep::Ap => "a'",
-        symm::Irrep::App => "a''",
-        symm::Irrep::A1 => "a_1",
-        symm::Irrep::B2 => "b_2",
-        symm::Irrep::B1 => "b_1",
-        symm::Irrep::A2 => "a_2",
-        symm::Irrep::Ag => "a_g",
-        symm::Irrep::B1g => "b_{1g}",
-        symm::Irrep::B2g => "b_{2g}",
-        symm::Irrep::B3g => "b_{3g}",
-        symm::Irrep::Au => "a_u",
-        symm::Irrep::B1u => "b_{1u}",
-        symm::Irrep::B2u => "b_{2u}",
-        symm::Irrep::B3u => "b_{3u}",
-        symm::Irrep::A1p => "a_1'",
-        symm::Irrep::A2p => "a_2'",
-        symm::Irrep::Ep => "e'",
-        symm::Irrep::A1pp => "a_1''",
-        symm::Irrep::A2pp => "a_2''",
-        symm::Irrep::Epp => "e''",
-        symm::Irrep::E1 => todo!(),
-        symm::Irrep::E2 => todo!(),
-        symm::Irrep::Bg => todo!(),
-        symm::Irrep::Bu => todo!(),
-        symm::Irrep::E1p => todo!(),
-        symm::Irrep::E2p => todo!(),
-        symm::Irrep::E => todo!(),
-    }
-}
+
+use taylor::{
+    cache::{reference_hash, EnergyCache},
+    consistency,
+    cuts::{Cut, Format},
+    cv, fcs,
+    fit::{Fit, FittedTaylor, Weighting},
+    generate,
+    harm::Harmonic,
+    internals::{check_sics, geom_ang, is_linear},
+    load_id_energies, ordering, parse_disp_id,
+    project::{Coord, Project},
+    spectro::CartFcs,
+    MergeError, Taylor, MAX_ORDER,
+};
+
+mod report;
+mod tex;
+
+use report::ReportFormat;
 
 /// generate intder and anpass input files with a taylor series expansion
 #[derive(Parser, Debug)]
@@ -61,41 +47,836 @@ struct Args {
     /// print the SICs in LaTeX for papers
     #[arg(short, long, default_value_t = false)]
     tex: bool,
+
+    /// fort.9903 file of fitted SIC force constants from anpass. if provided,
+    /// the quadratic force constants are used to print the harmonic
+    /// frequencies
+    #[arg(short, long)]
+    fcs: Option<String>,
+
+    /// write the force constants from --fcs, or from the project, to fort.15,
+    /// fort.30, and fort.40 for spectro
+    #[arg(long, default_value_t = false)]
+    spectro: bool,
+
+    /// which displacements to write to intder.in. the harmonic stage also
+    /// writes an anpass.in for the quadratic force constants alone
+    #[arg(long, value_enum, default_value_t = Stage::Full)]
+    stage: Stage,
+
+    /// truncation order of the Taylor series expansion. the default of 5
+    /// gives a quartic force field. the order must be from 1 to 128
+    #[arg(short = 'm', long, default_value_t = 5, value_parser = order)]
+    order: usize,
+
+    /// write only the displacements not already included in an expansion of
+    /// this lower truncation order, such as 5 when extending a quartic force
+    /// field to a sextic one. takes the place of the anharmonic stage
+    #[arg(long, conflicts_with = "stage", value_parser = order)]
+    extend: Option<usize>,
+
+    /// energy files from the earlier and later stages, one energy per line in
+    /// the order of their intder displacements. the earlier stage is either
+    /// the harmonic stage or the expansion given by --extend. these are
+    /// combined into the energies for the full anpass.in
+    #[arg(long, num_args = 2, value_names = ["EARLIER", "LATER"])]
+    merge: Option<Vec<String>>,
+
+    /// JSON project file recording the symmetry, expansion, energies, and
+    /// force constants. if it exists, the run resumes from it instead of
+    /// re-deriving the symmetry, and it is updated at the end of each run.
+    /// options that change the symmetry, like --subgroup, can't be combined
+    /// with an existing project, and changing --step-size discards its
+    /// energies
+    #[arg(short, long)]
+    project: Option<String>,
+
+    /// file of displacement ids and energies, one pair per line, as matched
+    /// against the ids.dat file written alongside intder.in. these are used as
+    /// the energies in anpass.in regardless of the displacement ordering
+    #[arg(long, conflicts_with = "merge")]
+    energies: Option<String>,
+
+    /// persistent energy cache shared between related runs. displacements
+    /// already in the cache are not written to intder.in, any --energies are
+    /// added to it, and it supplies the energies for anpass.in
+    #[arg(long, conflicts_with = "merge")]
+    cache: Option<String>,
+
+    /// fit the energies written to anpass.in and report the displacements
+    /// whose energies are inconsistent with the polynomial, those with a
+    /// studentized residual larger than this threshold or an excessive Cook's
+    /// distance
+    #[arg(long, num_args = 0..=1, default_missing_value = "3.0")]
+    diagnose: Option<f64>,
+
+    /// refit the energies with Boltzmann-like weights, exp(-ΔE / KT), on the
+    /// energy above the lowest one, with KT in hartree. anpass.in can't hold
+    /// weights, so the weighted force constants replace any from --fcs
+    #[arg(long, value_name = "KT", value_parser = positive)]
+    boltzmann: Option<f64>,
+
+    /// refit the energies with weights of 1 / (1 + (r / SCALE)²) on the
+    /// distance r of each displacement from the reference, with SCALE in Å
+    /// and radians. like --boltzmann, the weighted force constants replace
+    /// any from --fcs
+    #[arg(
+        long,
+        value_name = "SCALE",
+        value_parser = positive,
+        conflicts_with = "boltzmann"
+    )]
+    distance_weight: Option<f64>,
+
+    /// comma-separated truncation orders to compare by cross-validation,
+    /// fitting each to the energies of the full expansion given by --order.
+    /// like --order, each must be from 1 to 128
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "ORDERS",
+        value_parser = order
+    )]
+    cv: Option<Vec<usize>>,
+
+    /// also report the k-fold error with this many folds, at least 2, for
+    /// --cv, in addition to the leave-one-out error
+    #[arg(
+        long,
+        requires = "cv",
+        value_parser = at_least_two
+    )]
+    folds: Option<usize>,
+
+    /// write a cut through the surface along one SIC, or a grid over two, as
+    /// comma-separated, 1-based SIC indices from 1 to the number of SICs. the
+    /// cut compares the fitted energies with the computed ones on the
+    /// axis-aligned displacements
+    #[arg(long, value_delimiter = ',', num_args = 1..=2, requires = "write")]
+    cut: Option<Vec<usize>>,
+
+    /// replace the SICs in the input file with ones generated from the
+    /// connectivity and point group of the molecule, and write the new intder
+    /// header to this file. the rest of the run uses the generated SICs.
+    /// molecules in non-abelian point groups also need --subgroup
+    #[arg(long, value_name = "FILE")]
+    generate: Option<String>,
+
+    /// use this abelian subgroup of the detected point group for the irreps
+    /// of the SICs and the symmetry checks on the expansion instead of the
+    /// full group, such as when the SICs are only adapted to the subgroup. for
+    /// linear molecules, this is a subgroup of D2h or C2v. nonlinear D2h
+    /// molecules are reduced to C2v by default with --write, so pass
+    /// --subgroup d2h to keep the full group
+    #[arg(long, value_enum)]
+    subgroup: Option<Subgroup>,
+
+    /// the file format for --cut, written to cut.csv or cut.dat, respectively
+    #[arg(long, value_enum, default_value_t = CutFormat::Csv)]
+    cut_format: CutFormat,
+
+    /// write a summary of the geometry, point group, SICs, and expansion to
+    /// this file
+    #[arg(long, value_name = "FILE")]
+    report: Option<String>,
+
+    /// how to order the SICs in the output files
+    #[arg(long, value_enum, default_value_t = SicOrder::Irrep)]
+    sic_order: SicOrder,
+
+    /// comma-separated, 1-based indices of the input SICs in the order they
+    /// should appear in the output, overriding --sic-order
+    #[arg(long, value_delimiter = ',', value_name = "SICS")]
+    custom_order: Option<Vec<usize>>,
+
+    /// comma-separated, 1-based indices of the input SICs whose signs should
+    /// be reversed, such as to match the conventions of an earlier study
+    #[arg(long, value_delimiter = ',', value_name = "SICS")]
+    negate: Option<Vec<usize>>,
+
+    /// tolerance in hartree for the energies of displacements related by
+    /// symmetry, which are compared whenever energies are available
+    #[arg(long, default_value_t = 1e-8)]
+    symmetry_tol: f64,
+
+    /// the file format for --report
+    #[arg(long, value_enum, default_value_t = ReportFormat::Markdown)]
+    report_format: ReportFormat,
+}
+
+/// the output formats for --cut
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+enum CutFormat {
+    /// comma-separated values
+    Csv,
+    /// whitespace-separated columns for gnuplot
+    Gnuplot,
+}
+
+/// the abelian point groups that can be selected with --subgroup
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+enum Subgroup {
+    C1,
+    C2,
+    Cs,
+    C2v,
+    C2h,
+    D2h,
+}
+
+impl From<Subgroup> for Pg {
+    fn from(value: Subgroup) -> Self {
+        match value {
+            Subgroup::C1 => Pg::C1,
+            Subgroup::C2 => Pg::C2,
+            Subgroup::Cs => Pg::Cs,
+            Subgroup::C2v => Pg::C2v,
+            Subgroup::C2h => Pg::C2h,
+            Subgroup::D2h => Pg::D2h,
+        }
+    }
+}
+
+/// the orderings of the SICs
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+enum SicOrder {
+    /// the order of the input file
+    Keep,
+    /// grouped by irrep, keeping the input order within each irrep
+    Irrep,
+}
+
+/// the subset of displacements to generate
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+enum Stage {
+    /// all of the displacements
+    Full,
+    /// only the displacements needed for the quadratic force constants
+    Harmonic,
+    /// the displacements not already included in the harmonic stage
+    Anharmonic,
+}
+
+/// parse a positive, finite number for clap
+fn positive(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(x) if x.is_finite() && x > 0.0 => Ok(x),
+        Ok(_) => Err(format!("{s} is not a positive, finite number")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// parse a truncation order from 1 to [MAX_ORDER] for clap
+fn order(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(m) if (1..=MAX_ORDER).contains(&m) => Ok(m),
+        Ok(_) => Err(format!("{s} is not in 1..={MAX_ORDER}")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// parse a fold count of at least 2 for clap
+fn at_least_two(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(k) if k >= 2 => Ok(k),
+        Ok(_) => Err(format!("{s} is less than 2")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// load energies from `filename`, one per line, skipping blank lines
+fn load_energies(filename: &str) -> std::io::Result<Vec<f64>> {
+    let contents = std::fs::read_to_string(filename)?;
+    contents
+        .split_whitespace()
+        .map(|s| {
+            s.parse().map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("invalid energy {s} in {filename}: {e}"),
+                )
+            })
+        })
+        .collect()
+}
+
+/// return an error for input that is inconsistent with the rest of the run
+fn invalid_input(msg: impl Into<String>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, msg.into())
+}
+
+/// print the quality of `fit` and the displacements in `disps` it flags as
+/// outliers with `threshold`
+fn print_diagnostics(fit: &Fit, disps: &taylor::Disps, threshold: f64) {
+    println!("\nFit Diagnostics:");
+    println!(
+        "rms residual = {:.3e} with rank {} of {} terms",
+        fit.rms(),
+        fit.rank,
+        fit.coeffs.len()
+    );
+    let outliers = fit.outliers(threshold);
+    if outliers.is_empty() {
+        println!("no displacements flagged");
+        return;
+    }
+    println!("{} displacements should be recomputed:", outliers.len());
+    println!(
+        "{:>5} {:>20} {:>12} {:>10} {:>10} {:>10}",
+        "Job", "Id", "Residual", "Student", "Leverage", "Cook"
+    );
+    let ids = disps.ids();
+    for i in outliers {
+        println!(
+            "{:5} {:>20} {:12.3e} {:10.3} {:10.4} {:10.4}",
+            i + 1,
+            ids[i],
+            fit.residuals[i],
+            fit.studentized[i],
+            fit.leverage[i],
+            fit.cooks[i]
+        );
+    }
+}
+
+/// print the pairs of `disps` related by the symmetry of `pg` whose
+/// `energies` differ by more than `tol`
+fn print_mismatches(
+    pg: &symm::PointGroup,
+    irreps: &[symm::Irrep],
+    disps: &taylor::Disps,
+    energies: &[f64],
+    tol: f64,
+) {
+    let Some(mismatches) =
+        consistency::check_energies(pg, irreps, disps, energies, tol)
+    else {
+        eprintln!("can't check the symmetry of the energies in {pg}");
+        return;
+    };
+    if mismatches.is_empty() {
+        return;
+    }
+    println!(
+        "\n{} pairs of displacements related by symmetry have different \
+         energies. check for broken-symmetry solutions or a SIC with the \
+         wrong irrep:",
+        mismatches.len()
+    );
+    println!("{:>5} {:>5} {:>12}", "Job", "Image", "Difference");
+    for m in mismatches {
+        println!("{m}");
+    }
 }
 
 // this is pieced together from parts of pbqff, but it's not clear how to reuse
 // any of the parts
 fn main() -> std::io::Result<()> {
     let cfg = Args::parse();
+    if let Some(m) = cfg.extend.filter(|&m| m >= cfg.order) {
+        return Err(invalid_input(format!(
+            "--extend {m} must be less than the --order of {}",
+            cfg.order
+        )));
+    }
     // expects an Intder without dummy atoms
     let mut intder = Intder::load_file(&cfg.infile);
+    let resume = cfg.project.as_ref().filter(|p| Path::new(p).exists());
+    if let Some(path) = resume {
+        // these only affect deriving a new project
+        let ignored = [
+            ("--generate", cfg.generate.is_some()),
+            ("--subgroup", cfg.subgroup.is_some()),
+            ("--sic-order", cfg.sic_order != SicOrder::Irrep),
+            ("--custom-order", cfg.custom_order.is_some()),
+            ("--negate", cfg.negate.is_some()),
+        ];
+        if let Some((arg, _)) = ignored.iter().find(|(_, given)| *given) {
+            return Err(invalid_input(format!(
+                "{arg} can't be applied when resuming from {path}, remove it \
+                 to start a new project"
+            )));
+        }
+    }
+    if let Some(path) = &cfg.generate {
+        generate_sics(&cfg, &mut intder, path)?;
+    }
+    let mut project = if let Some(path) = resume {
+        eprintln!("resuming from {path}");
+        let project = Project::load(path)?;
+        project.apply(&mut intder);
+        report_sic_problems(&intder);
+        project
+    } else {
+        derive_project(&cfg, &mut intder)?
+    };
+    let just_irreps = project.irreps.clone();
+
+    println!("Normalized Geometry:\n{:20.12}", project.molecule());
+    println!("Point Group = {}", project.point_group);
+
+    let nsic = intder.symmetry_internals.len();
+    if let Some(&c) = cfg.cut.iter().flatten().find(|&&c| c < 1 || c > nsic) {
+        return Err(invalid_input(format!(
+            "--cut: SIC {c} is out of range for {nsic} SICs"
+        )));
+    }
+    if let Some(filename) = &cfg.fcs {
+        project.fcs = Some(fcs::load_9903(filename, nsic)?);
+    }
+
+    println!("\nSymmetry Internal Coordinates:");
+    if cfg.tex {
+        tex::print_sics(&intder, &just_irreps);
+    } else {
+        intder.print_sics(&mut std::io::stdout(), &just_irreps);
+    }
+
+    if cfg.write {
+        // the energies were computed at the saved step size, so they can't
+        // be reused with a different one
+        if project.step_size != cfg.step_size {
+            if project.energies.is_some() {
+                eprintln!(
+                    "warning: step size changed from {} to {}, discarding \
+                     the saved energies",
+                    project.step_size, cfg.step_size
+                );
+            }
+            project.taylor = None;
+            project.disps = None;
+            project.energies = None;
+        }
+        let checks = project.checks.clone();
+        let (taylor, taylor_disps) = match (&project.taylor, &project.disps) {
+            (Some(taylor), Some(disps)) if project.order == cfg.order => {
+                (taylor.clone(), disps.clone())
+            }
+            _ => {
+                // run taylor.py to get fcs and disps
+                let taylor = Taylor::try_new_par(
+                    cfg.order,
+                    nsic,
+                    checks.0.clone(),
+                    checks.1.clone(),
+                )
+                .map_err(|e| invalid_input(format!("--order: {e}")))?;
+                let disps = taylor.disps_par();
+                project.order = cfg.order;
+                project.taylor = Some(taylor.clone());
+                project.disps = Some(disps.clone());
+                project.energies = None;
+                (taylor, disps)
+            }
+        };
+        project.step_size = cfg.step_size;
+        if let Ok(census) =
+            Taylor::census(cfg.order, &just_irreps, &checks.0, &checks.1)
+        {
+            println!("\nForce Constants:\n{census}");
+        }
+        let (stage, (old_disps, new_disps)) = match cfg.extend {
+            Some(m) => {
+                let old = Taylor::try_new(
+                    m,
+                    nsic,
+                    checks.0.clone(),
+                    checks.1.clone(),
+                )
+                .map_err(|e| invalid_input(format!("--extend: {e}")))?
+                .disps();
+                let new = taylor.new_disps(&old);
+                (Stage::Anharmonic, (old, new))
+            }
+            None => (cfg.stage, taylor.split_disps()),
+        };
+
+        let mut cache =
+            cfg.cache.as_ref().map(EnergyCache::open).transpose()?;
+        let reference = reference_hash(&intder);
+        let written = match stage {
+            Stage::Full => &taylor_disps,
+            Stage::Harmonic => &old_disps,
+            Stage::Anharmonic => &new_disps,
+        };
+        let written = match &cache {
+            Some(cache) => cache.missing(reference, cfg.step_size, written),
+            None => written.clone(),
+        };
+        intder.disps = written.to_intder(cfg.step_size);
+
+        let mut f = std::fs::File::create("intder.in")?;
+        use std::io::Write;
+        writeln!(f, "{}", intder)?;
+
+        let mut f = std::fs::File::create("ids.dat")?;
+        for id in written.ids() {
+            writeln!(f, "{id}")?;
+        }
+
+        let (taylor, taylor_disps) = if stage == Stage::Harmonic {
+            (taylor.harmonic(), old_disps.clone())
+        } else {
+            (taylor, taylor_disps)
+        };
+        let energies = if let Some(cache) = &mut cache {
+            if let Some(file) = &cfg.energies {
+                for (id, energy) in load_id_energies(file)? {
+                    let disp = parse_disp_id(&id).ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("invalid displacement id {id} in {file}"),
+                        )
+                    })?;
+                    cache.insert(reference, cfg.step_size, &disp, energy);
+                }
+                cache.save()?;
+            }
+            cache
+                .energies(reference, cfg.step_size, &taylor_disps)
+                .unwrap_or_else(|_| {
+                    let n = cache
+                        .missing(reference, cfg.step_size, &taylor_disps)
+                        .len();
+                    eprintln!(
+                        "{n} displacements still need energies, \
+                         writing zeros to anpass.in"
+                    );
+                    vec![0.0; taylor_disps.len()]
+                })
+        } else if let Some(file) = &cfg.energies {
+            let energies = load_id_energies(file)?;
+            taylor_disps.energies_by_id(&energies).map_err(|id| {
+                invalid_input(format!(
+                    "no energy found for displacement {id} in {file}"
+                ))
+            })?
+        } else if let Some(files) = &cfg.merge {
+            let earlier = load_energies(&files[0])?;
+            let later = load_energies(&files[1])?;
+            taylor_disps
+                .merge_energies(&[(&old_disps, &earlier), (&new_disps, &later)])
+                .map_err(|e| match e {
+                    MergeError::LengthMismatch {
+                        part,
+                        disps,
+                        energies,
+                    } => invalid_input(format!(
+                        "{} has {energies} energies for {disps} displacements",
+                        files[part]
+                    )),
+                    e => invalid_input(format!("--merge: {e}")),
+                })?
+        } else if let Some(energies) = project
+            .energies
+            .as_ref()
+            .filter(|_| stage != Stage::Harmonic)
+        {
+            energies.clone()
+        } else {
+            vec![0.0; taylor_disps.len()]
+        };
+        if stage != Stage::Harmonic && energies.iter().any(|&e| e != 0.0) {
+            project.energies = Some(energies.clone());
+        }
+        let anpass = taylor.to_anpass(&taylor_disps, &energies, cfg.step_size);
+        let mut f = std::fs::File::create("anpass.in")?;
+        writeln!(f, "{}", anpass)?;
+
+        let weighting = match (cfg.boltzmann, cfg.distance_weight) {
+            (Some(kt), _) => Weighting::Boltzmann(kt),
+            (_, Some(scale)) => Weighting::Distance(scale),
+            _ => Weighting::Equal,
+        };
+        let have_energies = energies.iter().any(|&e| e != 0.0);
+        let fit = if !have_energies {
+            if cfg.diagnose.is_some() || weighting != Weighting::Equal {
+                eprintln!("no energies available to fit");
+            }
+            None
+        } else if cfg.diagnose.is_some()
+            || weighting != Weighting::Equal
+            || cfg.cut.is_some()
+        {
+            Some(Fit::weighted(&anpass, weighting))
+        } else {
+            None
+        };
+        if let Some(fit) = &fit {
+            if weighting != Weighting::Equal {
+                project.fcs = Some(fit.force_constants(&taylor.forces));
+            }
+            if let Some(threshold) = cfg.diagnose {
+                print_diagnostics(fit, &taylor_disps, threshold);
+            }
+        }
+
+        if have_energies {
+            print_mismatches(
+                &project.point_group,
+                &just_irreps,
+                &taylor_disps,
+                &energies,
+                cfg.symmetry_tol,
+            );
+        }
+
+        if let Some(coords) = &cfg.cut {
+            let fitted = match (&fit, &project.fcs) {
+                (Some(fit), _) => Some(fit.fitted(&taylor.forces)),
+                (None, Some(fcs)) => Some(FittedTaylor::from_fcs(fcs, nsic)),
+                (None, None) => None,
+            };
+            let coords: Vec<_> = coords.iter().map(|c| c - 1).collect();
+            let cut = Cut::new(
+                &coords,
+                &taylor_disps,
+                have_energies.then_some(energies.as_slice()),
+                fitted.as_ref(),
+                cfg.step_size,
+                None,
+            );
+            let (filename, format) = match cfg.cut_format {
+                CutFormat::Csv => ("cut.csv", Format::Csv),
+                CutFormat::Gnuplot => ("cut.dat", Format::Gnuplot),
+            };
+            cut.write(std::fs::File::create(filename)?, format)?;
+        }
+
+        if let Some(orders) = &cfg.cv {
+            if energies.iter().all(|&e| e == 0.0) {
+                eprintln!("no energies available for --cv");
+            } else {
+                println!("\nCross-Validation:");
+                print!(
+                    "{:>5} {:>5} {:>5} {:>12} {:>12}",
+                    "Order", "Terms", "Rank", "RMS", "LOO"
+                );
+                if let Some(k) = cfg.folds {
+                    print!(" {:>12}", format!("{k}-fold"));
+                }
+                println!();
+                for (m, score) in cv::select_order(
+                    orders,
+                    nsic,
+                    &checks,
+                    &taylor_disps,
+                    &energies,
+                    cfg.step_size,
+                    weighting,
+                    cfg.folds,
+                )
+                .map_err(|e| invalid_input(format!("--cv: {e}")))?
+                {
+                    println!("{m:5} {score}");
+                }
+            }
+        }
+    }
+
+    if let Some(fcs) = &project.fcs {
+        let fc2 = fcs::quadratic(fcs, nsic);
+        match Harmonic::new(&intder, &fc2, &just_irreps) {
+            Ok(harm) => println!("\nHarmonic Frequencies:\n{harm}"),
+            Err(e) => {
+                eprintln!("warning: skipping harmonic frequencies: {e}")
+            }
+        }
+        if cfg.spectro {
+            CartFcs::new(&intder, fcs)
+                .map_err(|e| invalid_input(format!("--spectro: {e}")))?
+                .write_spectro(".")?;
+        }
+    } else if cfg.spectro {
+        eprintln!("no force constants available for --spectro, use --fcs");
+    }
+
+    if let Some(path) = &cfg.report {
+        report::write(
+            std::fs::File::create(path)?,
+            &project,
+            cfg.report_format,
+        )?;
+    }
+
+    if let Some(path) = &cfg.project {
+        project.save(path)?;
+    }
+
+    Ok(())
+}
+
+/// return the molecule in `intder` in its normalized orientation
+fn normalized_molecule(intder: &Intder) -> Molecule {
     let pairs = intder.geom.0.iter().zip(&intder.atoms);
     let mut atoms = Vec::new();
     for (g, a) in pairs {
         atoms.push(Atom::new_from_label(&a.label, g[0], g[1], g[2]));
     }
-    let mol = {
-        let mut mol = Molecule::new(atoms);
-        // NOTE could actually check for linearity here by returning rotor type
-        // from normalize
-        mol.normalize();
-        mol
-    };
+    let mut mol = Molecule::new(atoms);
+    mol.normalize();
+    mol
+}
+
+/// return whether `mol` is symmetric under inversion through the origin,
+/// within `eps`
+fn is_centrosymmetric(mol: &Molecule, eps: f64) -> bool {
+    mol.atoms.iter().all(|a| {
+        mol.atoms.iter().any(|b| {
+            a.atomic_number == b.atomic_number
+                && (a.x + b.x).abs() < eps
+                && (a.y + b.y).abs() < eps
+                && (a.z + b.z).abs() < eps
+        })
+    })
+}
+
+/// replace the coordinates in `intder` with SICs generated from the
+/// connectivity of its normalized geometry, and write the resulting intder
+/// header to `path`
+fn generate_sics(
+    cfg: &Args,
+    intder: &mut Intder,
+    path: &str,
+) -> std::io::Result<()> {
+    let mol = normalized_molecule(intder);
+    let natoms = intder.atoms.len();
+    intder.geom = mol.clone().into();
+    // add_dummies may update the input options for the dummy atoms, which
+    // shouldn't be written to the header
+    let options = intder.input_options.clone();
+    let pg = mol.point_group_approx(cfg.eps);
+    // the SICs are only adapted to the axis-aligned symmetry operations, so
+    // their irreps can't be found in a non-abelian group
+    if cfg.subgroup.is_none()
+        && !is_linear(&geom_ang(intder), natoms)
+        && !is_abelian(&pg)
+    {
+        return Err(invalid_input(format!(
+            "--generate only supports abelian point groups, pass an abelian \
+             subgroup of {pg} with --subgroup"
+        )));
+    }
+    if let Some(axis) = pg.axis() {
+        intder.add_dummies(axis);
+    }
+    let labels: Vec<_> = intder.atoms.iter().map(|a| a.label.clone()).collect();
+    let (siics, sics) =
+        generate::generate(&labels, &geom_ang(intder), natoms, cfg.eps)
+            .map_err(|e| invalid_input(format!("--generate: {e}")))?;
+    eprintln!(
+        "generated {} SICs from {} simple internals",
+        sics.len(),
+        siics.len()
+    );
+    intder.input_options = options;
+    intder.input_options[1] = siics.len();
+    intder.input_options[2] = sics.len();
+    intder.simple_internals = siics;
+    intder.symmetry_internals = sics;
+    intder.geom.0.truncate(natoms);
+    intder.disps.clear();
+
+    let mut f = std::fs::File::create(path)?;
+    use std::io::Write;
+    writeln!(f, "{}", intder)
+}
+
+/// print a warning for each problem [check_sics] finds with the SICs in
+/// `intder`, or a message if they can't be checked
+fn report_sic_problems(intder: &Intder) {
+    match check_sics(
+        &intder.simple_internals,
+        &intder.symmetry_internals,
+        &geom_ang(intder),
+        intder.atoms.len(),
+    ) {
+        Ok(problems) => {
+            for problem in problems {
+                eprintln!("warning: {problem}");
+            }
+        }
+        Err(e) => eprintln!("not checking the SICs for completeness: {e}"),
+    }
+}
+
+/// report whether every operation of `pg` commutes with the others
+fn is_abelian(pg: &symm::PointGroup) -> bool {
+    use symm::PointGroup::*;
+    matches!(
+        pg,
+        C1 | C2 { .. } | Cs { .. } | C2v { .. } | C2h { .. } | D2h { .. }
+    )
+}
+
+/// normalize the geometry in `intder`, determine its point group and the
+/// irreps of its SICs, and order the SICs according to `cfg`. this leaves
+/// `intder` with the normalized geometry, any dummy atoms, and the ordered
+/// SICs
+fn derive_project(cfg: &Args, intder: &mut Intder) -> std::io::Result<Project> {
+    let mol = normalized_molecule(intder);
+    intder.geom = mol.clone().into();
+    let linear = is_linear(&geom_ang(intder), intder.atoms.len());
     let pg = {
         let mut pg = mol.point_group_approx(cfg.eps);
-        if cfg.write && pg.is_d2h() {
-            eprintln!(
-                "full point group is D2h, using C2v subgroup. \
-		       rerun without -w to print full symmetry"
-            );
-            pg = pg.subgroup(Pg::C2v).unwrap();
-        };
+        if linear {
+            // the infinite groups of linear molecules are handled through
+            // their largest abelian subgroups, which still separate the
+            // components of the degenerate bends
+            let (name, target) = if is_centrosymmetric(&mol, cfg.eps) {
+                ("D∞h", Pg::D2h)
+            } else {
+                ("C∞v", Pg::C2v)
+            };
+            match pg.subgroup(target) {
+                Ok(sub) => {
+                    pg = sub;
+                    eprintln!("linear molecule in {name}, using {pg} subgroup");
+                }
+                Err(e) => eprintln!(
+                    "warning: failed to find {target:?} subgroup of {pg} for \
+                     linear molecule in {name}: {e}. using {pg} instead"
+                ),
+            }
+            let lin1 = intder
+                .simple_internals
+                .iter()
+                .filter(|s| matches!(s, intder::Siic::Lin1(..)))
+                .count();
+            if lin1 % 2 != 0 || lin1 == 0 {
+                eprintln!(
+                    "warning: linear molecules need pairs of LIN1 \
+                     coordinates for the degenerate bends, try --generate"
+                );
+            }
+        }
+        match cfg.subgroup {
+            // the full group, which -w would otherwise reduce to C2v
+            Some(Subgroup::D2h) if pg.is_d2h() => (),
+            Some(sub) => {
+                pg = pg.subgroup(sub.into()).map_err(|e| {
+                    invalid_input(format!(
+                        "failed to find {sub:?} subgroup of {pg}: {e}"
+                    ))
+                })?;
+                eprintln!("using {pg} subgroup");
+            }
+            None if cfg.write && !linear && pg.is_d2h() => {
+                eprintln!(
+                    "full point group is D2h, using C2v subgroup. pass \
+                     --subgroup d2h to use the full group"
+                );
+                pg = pg.subgroup(Pg::C2v).map_err(|e| {
+                    invalid_input(format!(
+                        "failed to find C2v subgroup of {pg}: {e}"
+                    ))
+                })?;
+            }
+            None => (),
+        }
         pg
     };
 
-    println!("Normalized Geometry:\n{:20.12}", mol);
-    println!("Point Group = {}", pg);
-
     let nsic = intder.symmetry_internals.len();
     // generate a displacement for each SIC
     let mut disps = Vec::new();
@@ -105,13 +886,22 @@ fn main() -> std::io::Result<()> {
         disps.push(disp);
     }
     intder.disps = disps;
-    intder.geom = mol.clone().into();
+    let geom: Vec<_> =
+        intder.geom.0.iter().map(|g| [g[0], g[1], g[2]]).collect();
     let ndum = if let Some(axis) = pg.axis() {
         intder.add_dummies(axis)
     } else {
         0
     };
-    let disps = intder.convert_disps().unwrap();
+    // converting the displacements needs a well-posed set of SICs, so report
+    // any problems with them first
+    report_sic_problems(intder);
+    let disps = intder.convert_disps().map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("failed to convert the SIC displacements: {e}"),
+        )
+    })?;
 
     let atomic_numbers = mol.atomic_numbers();
     let mut irreps = Vec::new();
@@ -121,123 +911,70 @@ fn main() -> std::io::Result<()> {
             &atomic_numbers,
             &disp[..disp.len() - 3 * ndum],
         );
-        let irrep = match m.irrep_approx(&pg, cfg.eps) {
-            Ok(rep) => rep,
-            Err(e) => panic!("failed on coord {} with {}", i, e.msg()),
-        };
-        irreps.push((i, irrep));
+        let irrep = m.irrep_approx(&pg, cfg.eps).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "failed to find the irrep of SIC {} in {pg}: {}",
+                    i + 1,
+                    e.msg()
+                ),
+            )
+        })?;
+        irreps.push(irrep);
     }
-    // sort by irrep symmetry
-    irreps.sort_by_key(|k| k.1);
 
-    let just_irreps: Vec<_> = irreps.iter().map(|s| s.1).collect();
-
-    let mut new_sics = Vec::new();
-    for irrep in &irreps {
-        new_sics.push(intder.symmetry_internals[irrep.0].clone());
+    if let Some(negate) = &cfg.negate {
+        ordering::negate(&mut intder.symmetry_internals, negate)
+            .map_err(|e| invalid_input(format!("--negate: {e}")))?;
     }
-    intder.symmetry_internals = new_sics;
 
-    println!("\nSymmetry Internal Coordinates:");
-    if cfg.tex {
-        println!(r"\begin{{align}}");
-        let nsic = intder.symmetry_internals.len();
-        assert_eq!(intder.symmetry_internals.len(), just_irreps.len());
-        for (i, sic) in intder.symmetry_internals.iter().enumerate() {
-            let len = sic.iter().filter(|&&s| s != 0.0).count();
-            let (frac, close) = match len {
-                1 => ("", ""),
-                2 => (r"\frac{1}{\sqrt{2}}[", "]"),
-                _ => panic!("unrecognized number of simple internals, {len}"),
-            };
-            print!(
-                "S_{{{:<2}}}({}) &= & {}",
-                i + 1,
-                irrep(&just_irreps[i]),
-                frac
-            );
-            // number of siics printed so far
-            let mut nprt = 0;
-            for (j, s) in sic.iter().enumerate() {
-                if *s != 0.0 {
-                    if nprt > 0 {
-                        let sign = match s.signum() as isize {
-                            -1 => "-",
-                            1 => "+",
-                            _ => panic!("it's NaN"),
-                        };
-                        print!(" {sign} ");
-                    }
-                    let l = &intder.simple_internals[j];
-                    print!(
-                        "{}",
-                        match &l {
-                            intder::Siic::Stretch(a, b) => format!(
-                                "r(\\text{{{}}}_{}-\\text{{{}}}_{})",
-                                intder.atoms[*a].label,
-                                a + 1,
-                                intder.atoms[*b].label,
-                                b + 1
-                            ),
-                            intder::Siic::Bend(a, b, c) =>
-				format!(
-                                "\\angle(\\text{{{}}}_{}-\\text{{{}}}_{}-\\text{{{}}}_{})",
-                                intder.atoms[*a].label,
-                                a + 1,
-                                intder.atoms[*b].label,
-                                b + 1,
-                                intder.atoms[*c].label,
-                                c + 1
-                            ),
-                            intder::Siic::Torsion(a, b, c, d) =>
-				format!(
-                                    "\\tau(\\text{{{}}}_{}-\\text{{{}}}_{}\
-				     -\\text{{{}}}_{}-\\text{{{}}}_{})",
-                                intder.atoms[*a].label,
-                                a + 1,
-                                intder.atoms[*b].label,
-                                b + 1,
-                                intder.atoms[*c].label,
-                                c + 1,
-                                intder.atoms[*d].label,
-                                d + 1
-                            ),
-			    _ => panic!("tell brent to support {}", l),
-                            // intder::Siic::Lin1(_, _, _, _) => todo!(),
-                            // intder::Siic::Out(_, _, _, _) => todo!(),
-                        }
-                    );
-                    nprt += 1;
-                }
-            }
-            println!("{close}{}", if i < nsic - 1 { "\\\\" } else { "" });
+    // perm[i] is the input index of output SIC i
+    let perm = ordering::permutation(
+        &irreps,
+        cfg.custom_order.as_deref(),
+        cfg.sic_order == SicOrder::Irrep,
+    )
+    .map_err(|e| invalid_input(format!("--custom-order: {e}")))?;
+    if perm.iter().enumerate().any(|(i, &p)| i != p) {
+        println!("\nSIC Ordering:");
+        println!("{:>6} {:>6}", "Output", "Input");
+        for (i, p) in perm.iter().enumerate() {
+            println!("{:6} {:6}", i + 1, p + 1);
         }
-        println!(r"\end{{align}}");
-    } else {
-        intder.print_sics(&mut std::io::stdout(), &just_irreps);
     }
 
-    if cfg.write {
-        // generate checks
-        let checks = Taylor::make_checks(irreps, &pg);
-        // run taylor.py to get fcs and disps
-        let taylor = Taylor::new(5, nsic, checks.0, checks.1);
-        let taylor_disps = taylor.disps();
-
-        intder.disps = taylor_disps.to_intder(cfg.step_size);
-
-        let mut f = std::fs::File::create("intder.in")?;
-        use std::io::Write;
-        writeln!(f, "{}", intder)?;
+    let just_irreps: Vec<_> = perm.iter().map(|&i| irreps[i]).collect();
+    intder.symmetry_internals = perm
+        .iter()
+        .map(|&i| intder.symmetry_internals[i].clone())
+        .collect();
 
-        let anpass = taylor.to_anpass(
-            &taylor_disps,
-            &vec![0.0; taylor_disps.len()],
-            cfg.step_size,
-        );
-        let mut f = std::fs::File::create("anpass.in")?;
-        writeln!(f, "{}", anpass)?;
-    }
+    // generate checks from the positions of the SICs in the output order
+    let checks = Taylor::make_checks(
+        just_irreps.iter().copied().enumerate().collect(),
+        &pg,
+    );
 
-    Ok(())
+    let simple_internals = intder
+        .simple_internals
+        .iter()
+        .map(Coord::try_from)
+        .collect::<Result<_, _>>()
+        .map_err(invalid_input)?;
+    Ok(Project {
+        atoms: intder.atoms.iter().map(|a| a.label.clone()).collect(),
+        geom: geom[..intder.atoms.len()].to_vec(),
+        simple_internals,
+        symmetry_internals: intder.symmetry_internals.clone(),
+        point_group: pg,
+        irreps: just_irreps,
+        checks,
+        order: cfg.order,
+        step_size: cfg.step_size,
+        taylor: None,
+        disps: None,
+        energies: None,
+        fcs: None,
+    })
 }
@@ -0,0 +1,339 @@
+//! summary reports of a project for supporting information and wikis
+
+use std::io::Write;
+
+use intder::Siic;
+use symm::Irrep;
+use taylor::{
+    project::{Coord, Project},
+    Taylor,
+};
+
+/// the output formats for a report
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// GitHub-flavored Markdown
+    Markdown,
+    /// a standalone HTML page
+    Html,
+    /// plain text
+    Text,
+}
+
+/// render `sic` over the simple internals `siics` as a sum of its nonzero
+/// coefficients times each simple internal
+fn sic_text(siics: &[Coord], sic: &[f64]) -> String {
+    sic.iter()
+        .enumerate()
+        .filter(|(_, &c)| c != 0.0)
+        .map(|(j, c)| format!("{c:+.6} {}", Siic::from(&siics[j])))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// join `irreps` with spaces
+fn irrep_list(irreps: &[Irrep]) -> String {
+    irreps
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// escape the characters of `s` that are special in HTML
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// the text for the parts of a report that need the expansion, when it hasn't
+/// been generated
+const NOT_AVAILABLE: &str = "not available";
+
+/// a titled table in a report. the rows are None if the data for the table is
+/// not available
+struct Table {
+    title: &'static str,
+    header: Vec<&'static str>,
+    rows: Option<Vec<Vec<String>>>,
+}
+
+/// write a summary of `project` to `w` in `format`. the summary includes the
+/// normalized geometry, point group, SICs and their irreps, and the number of
+/// force constants kept and pruned by the symmetry checks and the number of
+/// displacements. the last two are reported as not available if the
+/// expansion hasn't been generated
+pub fn write(
+    mut w: impl Write,
+    project: &Project,
+    format: ReportFormat,
+) -> std::io::Result<()> {
+    let geom = format!("{:20.12}", project.molecule());
+    let pg = project.point_group.to_string();
+    let mut tables = vec![Table {
+        title: "Symmetry Internal Coordinates",
+        header: vec!["SIC", "Irrep", "Definition"],
+        rows: Some(
+            project
+                .symmetry_internals
+                .iter()
+                .zip(&project.irreps)
+                .enumerate()
+                .map(|(i, (sic, irrep))| {
+                    vec![
+                        format!("S{}", i + 1),
+                        irrep.to_string(),
+                        sic_text(&project.simple_internals, sic),
+                    ]
+                })
+                .collect(),
+        ),
+    }];
+    let census = project.taylor.as_ref().and_then(|_| {
+        Taylor::census(
+            project.order,
+            &project.irreps,
+            &project.checks.0,
+            &project.checks.1,
+        )
+        .ok()
+    });
+    let rows = census.map(|census| {
+        let mut rows = Vec::new();
+        let totals = census.totals();
+        for (order, groups) in &census.0 {
+            for (irreps, count) in groups {
+                rows.push(vec![
+                    order.to_string(),
+                    irrep_list(irreps),
+                    count.kept.to_string(),
+                    count.pruned.to_string(),
+                ]);
+            }
+            rows.push(vec![
+                order.to_string(),
+                "total".to_owned(),
+                totals[order].kept.to_string(),
+                totals[order].pruned.to_string(),
+            ]);
+        }
+        rows
+    });
+    tables.push(Table {
+        title: "Force Constants",
+        header: vec!["Order", "Irreps", "Kept", "Pruned"],
+        rows,
+    });
+    let ndisps = project
+        .disps
+        .as_ref()
+        .map_or(NOT_AVAILABLE.to_owned(), |d| d.len().to_string());
+
+    match format {
+        ReportFormat::Markdown => {
+            writeln!(w, "# Summary\n")?;
+            writeln!(w, "## Normalized Geometry\n\n```\n{geom}\n```\n")?;
+            writeln!(w, "Point Group = {pg}\n")?;
+            for t in &tables {
+                writeln!(w, "## {}\n", t.title)?;
+                let Some(rows) = &t.rows else {
+                    writeln!(w, "{NOT_AVAILABLE}\n")?;
+                    continue;
+                };
+                writeln!(w, "| {} |", t.header.join(" | "))?;
+                writeln!(w, "|{}", "---|".repeat(t.header.len()))?;
+                for row in rows {
+                    writeln!(w, "| {} |", row.join(" | "))?;
+                }
+                writeln!(w)?;
+            }
+            writeln!(w, "Displacements = {ndisps}")?;
+        }
+        ReportFormat::Html => {
+            writeln!(w, "<!DOCTYPE html>\n<html>\n<head>")?;
+            writeln!(w, "<meta charset=\"utf-8\">\n<title>Summary</title>")?;
+            writeln!(w, "</head>\n<body>\n<h1>Summary</h1>")?;
+            writeln!(w, "<h2>Normalized Geometry</h2>")?;
+            writeln!(w, "<pre>\n{}\n</pre>", escape(&geom))?;
+            writeln!(w, "<p>Point Group = {}</p>", escape(&pg))?;
+            for t in &tables {
+                writeln!(w, "<h2>{}</h2>", t.title)?;
+                let Some(rows) = &t.rows else {
+                    writeln!(w, "<p>{NOT_AVAILABLE}</p>")?;
+                    continue;
+                };
+                writeln!(w, "<table>")?;
+                write!(w, "<tr>")?;
+                for h in &t.header {
+                    write!(w, "<th>{h}</th>")?;
+                }
+                writeln!(w, "</tr>")?;
+                for row in rows {
+                    write!(w, "<tr>")?;
+                    for cell in row {
+                        write!(w, "<td>{}</td>", escape(cell))?;
+                    }
+                    writeln!(w, "</tr>")?;
+                }
+                writeln!(w, "</table>")?;
+            }
+            writeln!(w, "<p>Displacements = {ndisps}</p>")?;
+            writeln!(w, "</body>\n</html>")?;
+        }
+        ReportFormat::Text => {
+            writeln!(w, "Normalized Geometry:\n{geom}")?;
+            writeln!(w, "Point Group = {pg}")?;
+            for t in &tables {
+                writeln!(w, "\n{}:", t.title)?;
+                let Some(rows) = &t.rows else {
+                    writeln!(w, "{NOT_AVAILABLE}")?;
+                    continue;
+                };
+                let widths: Vec<_> = (0..t.header.len())
+                    .map(|j| {
+                        rows.iter()
+                            .map(|r| r[j].len())
+                            .chain([t.header[j].len()])
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    .collect();
+                let line = |cells: Vec<&str>| {
+                    cells
+                        .iter()
+                        .zip(&widths)
+                        .map(|(c, &n)| format!("{c:<n$}"))
+                        .collect::<Vec<_>>()
+                        .join("  ")
+                        .trim_end()
+                        .to_owned()
+                };
+                writeln!(w, "{}", line(t.header.clone()))?;
+                for row in rows {
+                    writeln!(
+                        w,
+                        "{}",
+                        line(row.iter().map(String::as_str).collect())
+                    )?;
+                }
+            }
+            writeln!(w, "\nDisplacements = {ndisps}")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use symm::{Axis, Plane, PointGroup};
+
+    use super::*;
+
+    /// a water project, with the expansion if `expand` is true
+    fn water(expand: bool) -> Project {
+        let pg = PointGroup::C2v {
+            axis: Axis::Z,
+            planes: [Plane(Axis::X, Axis::Z), Plane(Axis::Y, Axis::Z)],
+        };
+        let irreps = vec![Irrep::A1, Irrep::A1, Irrep::B2];
+        let checks = Taylor::make_checks(
+            irreps.iter().copied().enumerate().collect(),
+            &pg,
+        );
+        let taylor = Taylor::new(5, 3, checks.0.clone(), checks.1.clone());
+        let disps = taylor.disps();
+        Project {
+            atoms: vec!["H".to_owned(), "O".to_owned(), "H".to_owned()],
+            geom: vec![
+                [0.0, 1.431390244079, 0.986041163966],
+                [0.0, 0.0, -0.124238450265],
+                [0.0, -1.431390244079, 0.986041163966],
+            ],
+            simple_internals: vec![
+                Coord::Stretch(0, 1),
+                Coord::Stretch(1, 2),
+                Coord::Bend(0, 1, 2),
+            ],
+            symmetry_internals: vec![
+                vec![1.0, 1.0, 0.0],
+                vec![0.0, 0.0, 1.0],
+                vec![1.0, -1.0, 0.0],
+            ],
+            point_group: pg,
+            irreps,
+            checks,
+            order: 5,
+            step_size: 0.005,
+            taylor: expand.then_some(taylor),
+            disps: expand.then_some(disps),
+            energies: None,
+            fcs: None,
+        }
+    }
+
+    fn render(project: &Project, format: ReportFormat) -> String {
+        let mut buf = Vec::new();
+        write(&mut buf, project, format).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// check the parts of the report that don't depend on `format`
+    fn check(format: ReportFormat, want: [&str; 4]) {
+        let [sic, fc, expanded, missing] = want;
+        let project = water(true);
+        let ndisps = project.disps.as_ref().unwrap().len();
+        let got = render(&project, format);
+        assert!(got.contains(sic), "{got}");
+        assert!(got.contains(fc), "{got}");
+        assert!(got.contains(&expanded.replace("N", &ndisps.to_string())));
+        assert!(!got.contains(NOT_AVAILABLE), "{got}");
+
+        let got = render(&water(false), format);
+        assert!(got.contains(sic), "{got}");
+        assert!(got.contains(missing), "{got}");
+        assert!(!got.contains("total"), "{got}");
+    }
+
+    #[test]
+    fn markdown() {
+        check(
+            ReportFormat::Markdown,
+            [
+                "| S3 | B2 |",
+                "| Order | Irreps | Kept | Pruned |",
+                "Displacements = N",
+                "## Force Constants\n\nnot available\n\n\
+                 Displacements = not available",
+            ],
+        );
+    }
+
+    #[test]
+    fn html() {
+        check(
+            ReportFormat::Html,
+            [
+                "<tr><td>S3</td><td>B2</td>",
+                "<tr><th>Order</th><th>Irreps</th><th>Kept</th>",
+                "<p>Displacements = N</p>",
+                "<h2>Force Constants</h2>\n<p>not available</p>\n\
+                 <p>Displacements = not available</p>",
+            ],
+        );
+    }
+
+    #[test]
+    fn text() {
+        check(
+            ReportFormat::Text,
+            [
+                "S3   B2",
+                "Order  Irreps",
+                "\nDisplacements = N\n",
+                "Force Constants:\nnot available\n\n\
+                 Displacements = not available",
+            ],
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! LaTeX rendering of irreps and symmetry internal coordinates
+
+use intder::{Intder, Siic};
+
+// borrowed from summarize-bin
+pub fn irrep(ir: &symm::Irrep) -> &'static str {
+    match ir {
+        symm::Irrep::A => "a",
+        symm::Irrep::B => "b",
+        symm::Irrep::Ap => "a'",
+        symm::Irrep::App => "a''",
+        symm::Irrep::A1 => "a_1",
+        symm::Irrep::B2 => "b_2",
+        symm::Irrep::B1 => "b_1",
+        symm::Irrep::A2 => "a_2",
+        symm::Irrep::Ag => "a_g",
+        symm::Irrep::B1g => "b_{1g}",
+        symm::Irrep::B2g => "b_{2g}",
+        symm::Irrep::B3g => "b_{3g}",
+        symm::Irrep::Au => "a_u",
+        symm::Irrep::B1u => "b_{1u}",
+        symm::Irrep::B2u => "b_{2u}",
+        symm::Irrep::B3u => "b_{3u}",
+        symm::Irrep::A1p => "a_1'",
+        symm::Irrep::A2p => "a_2'",
+        symm::Irrep::Ep => "e'",
+        symm::Irrep::A1pp => "a_1''",
+        symm::Irrep::A2pp => "a_2''",
+        symm::Irrep::Epp => "e''",
+        symm::Irrep::E1 => "e_1",
+        symm::Irrep::E2 => "e_2",
+        symm::Irrep::Bg => "b_g",
+        symm::Irrep::Bu => "b_u",
+        symm::Irrep::E1p => "e_1'",
+        symm::Irrep::E2p => "e_2'",
+        symm::Irrep::E => "e",
+    }
+}
+
+/// the label of atom `i` in `intder`, as `\text{H}_1`. atoms past the real
+/// ones are the dummy atoms, labeled X
+fn atom(intder: &Intder, i: usize) -> String {
+    let label = intder.atoms.get(i).map_or("X", |a| a.label.as_str());
+    format!("\\text{{{}}}_{}", label, i + 1)
+}
+
+/// the atoms in `idx` joined by bonds
+fn chain(intder: &Intder, idx: &[usize]) -> String {
+    idx.iter()
+        .map(|&i| atom(intder, i))
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// render `siic` from `intder` in LaTeX
+pub fn siic(intder: &Intder, siic: &Siic) -> String {
+    match *siic {
+        Siic::Stretch(a, b) => format!("r({})", chain(intder, &[a, b])),
+        Siic::Bend(a, b, c) => {
+            format!("\\angle({})", chain(intder, &[a, b, c]))
+        }
+        Siic::Torsion(a, b, c, d) => {
+            format!("\\tau({})", chain(intder, &[a, b, c, d]))
+        }
+        Siic::Lin1(a, b, c, d) => format!(
+            "\\theta({};{})",
+            chain(intder, &[a, b, c]),
+            atom(intder, d)
+        ),
+        Siic::Out(a, b, c, d) => {
+            format!("\\gamma({})", chain(intder, &[a, b, c, d]))
+        }
+        Siic::Linx(a, b, c, d) => {
+            format!("\\theta_x({})", chain(intder, &[a, b, c, d]))
+        }
+        Siic::Liny(a, b, c, d) => {
+            format!("\\theta_y({})", chain(intder, &[a, b, c, d]))
+        }
+    }
+}
+
+/// return `x` as an integer if it is within rounding error of one
+fn as_int(x: f64) -> Option<i64> {
+    let r = x.round();
+    ((x - r).abs() < 1e-8 * x.abs().max(1.0)).then_some(r as i64)
+}
+
+/// return the normalization prefix for integer coefficients whose squares
+/// sum to `norm2`, like \frac{1}{\sqrt{2}}
+fn prefix(norm2: i64) -> String {
+    let root = (norm2 as f64).sqrt().round() as i64;
+    if norm2 == 1 {
+        String::new()
+    } else if root * root == norm2 {
+        format!("\\frac{{1}}{{{root}}}")
+    } else {
+        format!("\\frac{{1}}{{\\sqrt{{{norm2}}}}}")
+    }
+}
+
+/// render the SIC with coefficients `sic` over the simple internals of
+/// `intder` in LaTeX, with the normalization intder applies made explicit, as
+/// in [combination]
+pub fn sic(intder: &Intder, sic: &[f64]) -> String {
+    let terms: Vec<_> = sic
+        .iter()
+        .enumerate()
+        .filter(|(_, &c)| c != 0.0)
+        .map(|(j, &c)| (siic(intder, &intder.simple_internals[j]), c))
+        .collect();
+    combination(&terms)
+}
+
+/// render the linear combination of the named `terms` and their coefficients,
+/// normalized like intder does. coefficients that are integer multiples of the
+/// smallest one are factored into a prefix like \frac{1}{\sqrt{6}}, and any
+/// others are written as normalized decimals. an empty combination, from an
+/// all-zero SIC, is written as 0
+fn combination(terms: &[(String, f64)]) -> String {
+    if terms.is_empty() {
+        return String::from("0");
+    }
+    let smallest = terms.iter().map(|t| t.1.abs()).fold(f64::MAX, f64::min);
+    let ints: Option<Vec<i64>> =
+        terms.iter().map(|t| as_int(t.1 / smallest)).collect();
+    let (pre, coeffs): (String, Vec<String>) = match ints {
+        Some(ints) => (
+            prefix(ints.iter().map(|i| i * i).sum()),
+            ints.iter()
+                .map(|i| match i.abs() {
+                    1 => String::new(),
+                    n => n.to_string(),
+                })
+                .collect(),
+        ),
+        None => {
+            let norm = terms.iter().map(|t| t.1 * t.1).sum::<f64>().sqrt();
+            (
+                String::new(),
+                terms
+                    .iter()
+                    .map(|t| format!("{:.4}", (t.1 / norm).abs()))
+                    .collect(),
+            )
+        }
+    };
+    let mut ret = String::new();
+    for (k, ((name, c), coeff)) in terms.iter().zip(coeffs).enumerate() {
+        match (k, c.is_sign_negative()) {
+            (0, true) => ret.push('-'),
+            (0, false) => {}
+            (_, true) => ret.push_str(" - "),
+            (_, false) => ret.push_str(" + "),
+        }
+        ret.push_str(&coeff);
+        ret.push_str(name);
+    }
+    if terms.len() > 1 && !pre.is_empty() {
+        format!("{pre}[{ret}]")
+    } else {
+        format!("{pre}{ret}")
+    }
+}
+
+/// print the SICs of `intder` and their `irreps` as a LaTeX align
+/// environment
+pub fn print_sics(intder: &Intder, irreps: &[symm::Irrep]) {
+    println!(r"\begin{{align}}");
+    let nsic = intder.symmetry_internals.len();
+    assert_eq!(nsic, irreps.len());
+    for (i, s) in intder.symmetry_internals.iter().enumerate() {
+        println!(
+            "S_{{{:<2}}}({}) &= & {}{}",
+            i + 1,
+            irrep(&irreps[i]),
+            sic(intder, s),
+            if i < nsic - 1 { "\\\\" } else { "" }
+        );
+    }
+    println!(r"\end{{align}}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(coeffs: &[f64]) -> Vec<(String, f64)> {
+        ["a", "b", "c", "d"]
+            .iter()
+            .zip(coeffs)
+            .map(|(n, &c)| (n.to_string(), c))
+            .collect()
+    }
+
+    #[test]
+    fn prefixes() {
+        assert_eq!(prefix(1), "");
+        assert_eq!(prefix(2), "\\frac{1}{\\sqrt{2}}");
+        assert_eq!(prefix(4), "\\frac{1}{2}");
+        assert_eq!(prefix(6), "\\frac{1}{\\sqrt{6}}");
+    }
+
+    #[test]
+    fn combinations() {
+        assert_eq!(combination(&terms(&[0.5])), "a");
+        assert_eq!(combination(&terms(&[-2.0])), "-a");
+        assert_eq!(
+            combination(&terms(&[1.0, -1.0])),
+            "\\frac{1}{\\sqrt{2}}[a - b]"
+        );
+        assert_eq!(
+            combination(&terms(&[1.0, 1.0, 1.0, 1.0])),
+            "\\frac{1}{2}[a + b + c + d]"
+        );
+        // non-unit integer multiples are factored out
+        assert_eq!(
+            combination(&terms(&[2.0, -1.0, -1.0])),
+            "\\frac{1}{\\sqrt{6}}[2a - b - c]"
+        );
+        assert_eq!(
+            combination(&terms(&[0.5, 1.0])),
+            "\\frac{1}{\\sqrt{5}}[a + 2b]"
+        );
+        // and other coefficients are normalized
+        assert_eq!(combination(&terms(&[1.0, 1.5])), "0.5547a + 0.8321b");
+        assert_eq!(combination(&[]), "0");
+    }
+}